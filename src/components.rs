@@ -14,15 +14,85 @@ pub struct Player;
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub struct Enemy;
 
+/// Energy spent on a move while sneaking; quieter, but slower.
+pub const SNEAK_MOVE_COST: i32 = ACTION_COST * 3 / 2;
+
+/// How noticeable the player is. Noise is set by the player's last action and
+/// carries further than they can be seen in the dark.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Stealth {
+    pub sneaking: bool,
+    pub noise: i32,
+}
+
+impl Stealth {
+    /// Percent chance a monster right next to the player notices them.
+    pub fn exposure(self, lit: bool) -> i32 {
+        let light = if lit { 60 } else { 25 };
+        i32::min(100, light + self.noise * 10)
+    }
+}
+
+/// Monsters that have noticed the player and are hunting them.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Aware;
+
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub struct MovingRandmly;
 
+/// Entities that no other blocking entity may share a tile with.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BlocksTile;
+
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub struct WantsToMove {
     pub entity: Entity,
     pub destination: Point,
 }
 
+/// How a creature gets about, which decides the terrain it can cross.
+/// Creatures without one walk, opening any doors in their way.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Mobility {
+    /// Flyers pass over water, lava and chasms unharmed.
+    pub flying: bool,
+    /// Swimmers can cross deep water.
+    pub swimming: bool,
+    /// Monsters too dumb or clumsy to open doors treat closed ones as walls.
+    pub opens_doors: bool,
+}
+
+impl Default for Mobility {
+    fn default() -> Self {
+        Self {
+            flying: false,
+            swimming: false,
+            opens_doors: true,
+        }
+    }
+}
+
+/// Closes the open door at `pos`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct WantsToCloseDoor {
+    pub pos: Point,
+}
+
+/// The player is searching the tiles around them for secret doors and hidden
+/// traps, for a few more turns unless interrupted.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Searching {
+    pub turns_left: i32,
+}
+
+/// Swaps the positions of two entities, such as the player and a companion
+/// they bump into.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct WantsToSwap {
+    pub entity: Entity,
+    pub other: Entity,
+}
+
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub struct Health {
     pub current: i32,
@@ -32,6 +102,40 @@ pub struct Health {
 #[derive(Clone, PartialEq)]
 pub struct Name(pub String);
 
+/// Energy an actor must build up before it may take an action.
+pub const ACTION_COST: i32 = 100;
+/// Energy gained per tick by an actor moving at the player's pace.
+pub const NORMAL_SPEED: i32 = 10;
+
+/// Actors gain `speed` energy every tick of game time, and act whenever their
+/// energy reaches `ACTION_COST`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Energy {
+    pub current: i32,
+    pub speed: i32,
+}
+
+impl Energy {
+    pub fn new(speed: i32) -> Self {
+        Self { current: 0, speed }
+    }
+
+    pub fn is_ready(self) -> bool {
+        self.current >= ACTION_COST
+    }
+
+    /// Ticks of game time until the actor can act again.
+    pub fn ticks_until_ready(self) -> i32 {
+        if self.is_ready() {
+            return 0;
+        }
+        if self.speed <= 0 {
+            return i32::MAX;
+        }
+        (ACTION_COST - self.current + self.speed - 1) / self.speed
+    }
+}
+
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub struct WantsToAttack {
     pub attacker: Entity,
@@ -41,12 +145,153 @@ pub struct WantsToAttack {
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub struct ChasingPlayer;
 
+/// Monsters sharing the same pack id hunt together and try to surround the
+/// player.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Pack(pub usize);
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Spell {
+    /// Restores health to a wounded ally.
+    Heal,
+    /// Calls goblins onto the free tiles around the caster.
+    Summon,
+    /// Lets an ally act twice as often for a while.
+    Haste,
+}
+
+impl Spell {
+    pub fn mana_cost(self) -> i32 {
+        match self {
+            Spell::Heal => 2,
+            Spell::Summon => 4,
+            Spell::Haste => 3,
+        }
+    }
+}
+
+/// Monsters that cast spells from their list, paid for out of their `Mana`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Spellcaster {
+    pub spells: Vec<Spell>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Mana {
+    pub current: i32,
+    pub max: i32,
+}
+
+/// A spell on its way to being resolved, sent as a message entity just like
+/// `WantsToAttack`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct WantsToCast {
+    pub caster: Entity,
+    pub spell: Spell,
+    pub target: Entity,
+}
+
+/// Hasted actors gain energy twice as fast until the effect wears off.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Hasted {
+    pub ticks_left: i32,
+}
+
+/// Allied companions travel with their leader and fight the monsters they
+/// can see.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Follower {
+    pub leader: Entity,
+}
+
+/// Scripted stages of a boss fight, advanced as the boss loses health.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BossPhase {
+    /// Waiting by the amulet for the player to show up.
+    Guarding,
+    /// Below half health, the boss has called in minions.
+    Rallying,
+    /// Below a quarter health, the boss moves twice as fast.
+    Enraged,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Boss {
+    pub phase: BossPhase,
+}
+
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub struct Item;
 
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub struct AmuletOfYala;
 
+/// Items that restore health to whoever uses them.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ProvidesHealing {
+    pub amount: i32,
+}
+
+/// Tools that let whoever carries them dig through walls by walking into
+/// them.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DigsWalls;
+
+/// Items that blow apart the walls around whoever sets them off.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Explosive {
+    pub radius: i32,
+}
+
+/// Items in an inventory rather than lying on the map.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Carried(pub Entity);
+
+/// Message to use up an item, resolved like `WantsToAttack`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ActivateItem {
+    pub used_by: Entity,
+    pub item: Entity,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TrapKind {
+    /// Wounds whoever steps on them.
+    Spikes,
+    /// Whisks its victim away to a random spot on the level.
+    Teleport,
+    /// Wakes every monster on the level.
+    Alarm,
+    /// Hurts its victim, who loses a turn climbing back out.
+    Pit,
+}
+
+/// Traps go off whenever a creature moves onto them, and stay armed.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Trap {
+    pub kind: TrapKind,
+}
+
+/// Traps nobody has spotted yet. Hidden traps are not drawn, and monsters
+/// don't know to avoid them.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Hidden;
+
+/// Message sent by the movement system when a creature lands on a trap.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TrapTriggered {
+    pub trap: Entity,
+    pub victim: Entity,
+}
+
+/// Message sent by the movement system when a creature that can't fly moves
+/// onto water, lava or a chasm, leaving `from` behind.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct EnteredTerrain {
+    pub entity: Entity,
+    pub from: Point,
+}
+
 #[derive(Clone, Debug, PartialEq)]
 pub struct FieldOfView {
     pub visible_tiles: HashSet<Point>,
@@ -0,0 +1,63 @@
+use std::collections::HashMap;
+
+use crate::prelude::*;
+
+/// Caches the Dijkstra flow field leading to the player, so every AI system
+/// can share a single computation per turn. The field is only rebuilt when the
+/// player moves or the map changes.
+///
+/// Occupied tiles are weighted as they were when the field was built, so the
+/// field does not follow other creatures around while the player stands still.
+///
+/// Creatures that get about differently, such as flyers, take different
+/// routes, so each kind of mobility has a field of its own.
+pub struct FlowFieldCache {
+    player_fields: HashMap<Mobility, CachedField>,
+}
+
+struct CachedField {
+    player_pos: Point,
+    map_revision: usize,
+    field: DijkstraMap,
+}
+
+impl FlowFieldCache {
+    pub fn new() -> Self {
+        Self {
+            player_fields: HashMap::new(),
+        }
+    }
+
+    /// Rebuild the player flow field for creatures with the given mobility if
+    /// it is missing or out of date.
+    pub fn update(&mut self, map: &Map, player_pos: Point, mobility: Mobility) {
+        let is_current = self.player_fields.get(&mobility).is_some_and(|cached| {
+            cached.player_pos == player_pos && cached.map_revision == map.revision
+        });
+        if is_current {
+            return;
+        }
+
+        let search_targets = vec![map.point2d_to_index(player_pos)];
+        let view = map.view(mobility);
+        self.player_fields.insert(
+            mobility,
+            CachedField {
+                player_pos,
+                map_revision: map.revision,
+                field: DijkstraMap::new(map.width, map.height, &search_targets, &view, 1024.0),
+            },
+        );
+    }
+
+    /// Flow field leading towards the player for creatures with the given
+    /// mobility. Following the lowest exit from any tile walks towards the
+    /// player; the highest walks away from them.
+    pub fn player_field(&self, mobility: Mobility) -> &DijkstraMap {
+        &self
+            .player_fields
+            .get(&mobility)
+            .expect("Flow field used before it was built")
+            .field
+    }
+}
@@ -0,0 +1,252 @@
+use std::collections::HashMap;
+
+use crate::prelude::*;
+
+/// Number of dungeon levels; the amulet waits on the last of them.
+pub const NUM_LEVELS: usize = 3;
+/// Size of the first level, in tiles. The camera scrolls over whatever part
+/// of a level the screen can't fit.
+const LEVEL_WIDTH: i32 = 80;
+const LEVEL_HEIGHT: i32 = 50;
+/// Tiles each level grows by, across and down, over the one above it.
+const LEVEL_WIDTH_GROWTH: i32 = 10;
+const LEVEL_HEIGHT_GROWTH: i32 = 6;
+
+/// How far down the dungeon the player is, counting from zero at the
+/// entrance.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Depth(pub usize);
+
+impl Depth {
+    pub fn is_final(self) -> bool {
+        self.0 + 1 >= NUM_LEVELS
+    }
+
+    /// Width and height of the level at this depth. Deeper levels sprawl
+    /// further, so there is more to explore the further down the player gets.
+    pub fn level_size(self) -> (i32, i32) {
+        let depth = i32::try_from(self.0).unwrap();
+        (
+            LEVEL_WIDTH + depth * LEVEL_WIDTH_GROWTH,
+            LEVEL_HEIGHT + depth * LEVEL_HEIGHT_GROWTH,
+        )
+    }
+}
+
+/// A level the player has left, with the entities that stayed behind on it
+/// moved out of the live world. Moving entities between legion worlds keeps
+/// their ids, so components that refer to other entities stay valid.
+pub struct StoredLevel {
+    pub map: Map,
+    pub theme: Box<dyn MapTheme>,
+    pub world: World,
+}
+
+/// The levels the player isn't on, by depth.
+#[derive(Default)]
+pub struct LevelStore {
+    levels: HashMap<usize, StoredLevel>,
+}
+
+impl LevelStore {
+    pub fn store(&mut self, depth: Depth, level: StoredLevel) {
+        self.levels.insert(depth.0, level);
+    }
+
+    pub fn take(&mut self, depth: Depth) -> Option<StoredLevel> {
+        self.levels.remove(&depth.0)
+    }
+}
+
+/// Generates the dungeon level at `depth`, populates it and sets up the
+/// resources the systems need to play it. A player already in the world came
+/// down the stairs, so they arrive by the stairs back up along with their
+/// companions; otherwise a new one is spawned. Fails, leaving the world
+/// untouched, if no playable level could be generated.
+pub fn build_level(
+    ecs: &mut World,
+    resources: &mut Resources,
+    depth: Depth,
+) -> Result<(), MapGenError> {
+    let mut rng = RandomNumberGenerator::new();
+    let (width, height) = depth.level_size();
+    let mut map_builder = MapBuilder::new(&mut rng, width, height)?;
+
+    let arrival = if has_player(ecs) {
+        let stairs = map_builder.player_start;
+        let stairs_idx = map_builder.map.point2d_to_index(stairs);
+        map_builder.map.tiles[stairs_idx] = TileType::UpStairs;
+        move_party(ecs, &mut map_builder.map, stairs)
+    } else {
+        let player = spawn_player(ecs, map_builder.player_start);
+        spawn_companion(ecs, player, map_builder.companion_start);
+        // Claim the tiles taken so far, so monster packs gather around them.
+        for pos in [map_builder.player_start, map_builder.companion_start] {
+            map_builder.map.set_occupied(pos, true);
+        }
+        map_builder.player_start
+    };
+
+    // The amulet and its guardian wait at the bottom; every other level leads
+    // further down instead.
+    if depth.is_final() {
+        spawn_amulet_of_yala(ecs, map_builder.amulet_start);
+        spawn_boss(ecs, map_builder.boss_start);
+        map_builder.map.set_occupied(map_builder.boss_start, true);
+    } else {
+        let exit_idx = map_builder.map.point2d_to_index(map_builder.amulet_start);
+        map_builder.map.tiles[exit_idx] = TileType::Exit;
+        map_builder.add_chasm(&mut rng);
+    }
+
+    let theme = map_builder.theme.as_ref();
+    for pos in &map_builder.vault_monsters {
+        spawn_monster(ecs, &mut rng, theme, *pos);
+        map_builder.map.set_occupied(*pos, true);
+    }
+    map_builder
+        .monster_spawns
+        .iter()
+        .enumerate()
+        .for_each(|(pack_id, pos)| {
+            spawn_encounter(ecs, &mut rng, &mut map_builder.map, theme, *pos, pack_id);
+        });
+    for pos in &map_builder.item_spawns {
+        spawn_item(ecs, &mut rng, *pos);
+    }
+    for pos in &map_builder.trap_spawns {
+        spawn_trap(ecs, &mut rng, *pos);
+    }
+
+    insert_level_resources(
+        ecs,
+        resources,
+        map_builder.map,
+        map_builder.theme,
+        arrival,
+        depth,
+    );
+    Ok(())
+}
+
+/// Brings a stored level back into the live world, with the player arriving
+/// by the stairs that lead back to the depth they came from.
+pub fn restore_level(
+    ecs: &mut World,
+    resources: &mut Resources,
+    depth: Depth,
+    mut level: StoredLevel,
+    came_from: Depth,
+) {
+    ecs.move_from(&mut level.world, &any());
+    let mut map = level.map;
+
+    // Occupancy is rebuilt every turn, but the player needs a free tile to
+    // arrive on before then.
+    map.clear_occupied();
+    <&Point>::query()
+        .filter(component::<BlocksTile>() & !component::<Player>() & !component::<Follower>())
+        .iter(ecs)
+        .for_each(|pos| map.set_occupied(*pos, true));
+
+    let stairs_tile = if came_from.0 > depth.0 {
+        TileType::Exit
+    } else {
+        TileType::UpStairs
+    };
+    let stairs = map
+        .tiles
+        .iter()
+        .position(|tile| *tile == stairs_tile)
+        .map_or(Point::zero(), |idx| map.index_to_point2d(idx));
+    let arrival = move_party(ecs, &mut map, stairs);
+
+    insert_level_resources(ecs, resources, map, level.theme, arrival, depth);
+}
+
+fn insert_level_resources(
+    ecs: &World,
+    resources: &mut Resources,
+    map: Map,
+    theme: Box<dyn MapTheme>,
+    arrival: Point,
+    depth: Depth,
+) {
+    // Turns rebuild the index, but the first round of input already needs
+    // to see what is on the level.
+    let mut spatial_index = SpatialIndex::new(map.width, map.height);
+    <(Entity, &Point)>::query()
+        .iter(ecs)
+        .for_each(|(entity, pos)| spatial_index.add(*pos, *entity));
+    resources.insert(spatial_index);
+    resources.insert(map);
+    resources.insert(theme);
+    resources.insert(FlowFieldCache::new());
+    resources.insert(Camera::new(arrival));
+    resources.insert(depth);
+    resources.insert(TurnState::AwaitingInput);
+}
+
+fn has_player(ecs: &World) -> bool {
+    <&Player>::query().iter(ecs).next().is_some()
+}
+
+/// Moves the player and their companions next to the stairs they arrived
+/// by, rather than onto them, so waiting a turn doesn't send them straight
+/// back. Returns where the player ended up.
+fn move_party(ecs: &mut World, map: &mut Map, stairs: Point) -> Point {
+    let player = <Entity>::query()
+        .filter(component::<Player>())
+        .iter(ecs)
+        .copied()
+        .next()
+        .unwrap();
+    let followers: Vec<Entity> = <(Entity, &Follower)>::query()
+        .iter(ecs)
+        .filter(|(_, follower)| follower.leader == player)
+        .map(|(entity, _)| *entity)
+        .collect();
+
+    let arrival = free_tile_near(map, stairs);
+    map.set_occupied(arrival, true);
+    let mut party = vec![(player, arrival)];
+    for follower in followers {
+        let pos = free_tile_near(map, arrival);
+        map.set_occupied(pos, true);
+        party.push((follower, pos));
+    }
+
+    for (entity, pos) in party {
+        let Some(mut entry) = ecs.entry(entity) else {
+            continue;
+        };
+        entry.add_component(pos);
+        if let Ok(fov) = entry.get_component_mut::<FieldOfView>() {
+            *fov = fov.clone_dirty();
+        }
+    }
+    // The player arrives ready to act.
+    if let Some(mut entry) = ecs.entry(player) {
+        if let Ok(energy) = entry.get_component_mut::<Energy>() {
+            energy.current = ACTION_COST;
+        }
+    }
+    arrival
+}
+
+/// A free floor tile next to `pos`, or `pos` itself if it is boxed in.
+fn free_tile_near(map: &Map, pos: Point) -> Point {
+    [
+        Point::new(-1, 0),
+        Point::new(1, 0),
+        Point::new(0, -1),
+        Point::new(0, 1),
+    ]
+    .iter()
+    .map(|delta| pos + *delta)
+    .find(|pos| {
+        map.try_idx(*pos)
+            .is_some_and(|idx| map.tiles[idx] == TileType::Floor && !map.occupied[idx])
+    })
+    .unwrap_or(pos)
+}
@@ -2,8 +2,11 @@
 
 mod camera;
 mod components;
+mod flow_field;
+mod levels;
 mod map;
 mod map_builder;
+mod spatial_index;
 mod spawner;
 mod systems;
 mod turn_state;
@@ -14,11 +17,12 @@ mod prelude {
     pub use legion::world::*;
     pub use legion::*;
 
-    pub const MAP_WIDTH: i32 = 80;
-    pub const MAP_HEIGHT: i32 = 50;
-    pub const MAP_SIZE: i32 = MAP_WIDTH * MAP_HEIGHT;
-    pub const DISPLAY_WIDTH: i32 = MAP_WIDTH / 2;
-    pub const DISPLAY_HEIGHT: i32 = MAP_HEIGHT / 2;
+    /// Size of the window, in text cells of the small font. Maps carry
+    /// their own size, and the camera shows part of them at a time.
+    pub const SCREEN_WIDTH: i32 = 80;
+    pub const SCREEN_HEIGHT: i32 = 50;
+    pub const DISPLAY_WIDTH: i32 = SCREEN_WIDTH / 2;
+    pub const DISPLAY_HEIGHT: i32 = SCREEN_HEIGHT / 2;
 
     pub enum Layers {
         Map,
@@ -28,8 +32,11 @@ mod prelude {
 
     pub use crate::camera::*;
     pub use crate::components::*;
+    pub use crate::flow_field::*;
+    pub use crate::levels::*;
     pub use crate::map::*;
     pub use crate::map_builder::*;
+    pub use crate::spatial_index::*;
     pub use crate::spawner::*;
     pub use crate::systems::*;
     pub use crate::turn_state::*;
@@ -43,33 +50,22 @@ struct State {
     input_systems: Schedule,
     player_systems: Schedule,
     monster_systems: Schedule,
+    levels: LevelStore,
 }
 impl State {
-    fn new() -> Self {
+    fn new() -> Result<Self, MapGenError> {
         let mut ecs = World::default();
         let mut resources = Resources::default();
-        let mut rng = RandomNumberGenerator::new();
-        let map_builder = MapBuilder::new(&mut rng);
-
-        // Spawn monsters in the center of every room except the first room
-        // for the starting player.
-        map_builder.rooms.iter().skip(1).for_each(|r| {
-            spawn_monster(&mut ecs, &mut rng, r.center());
-        });
-
-        resources.insert(map_builder.map);
-        resources.insert(Camera::new(map_builder.player_start));
-        resources.insert(TurnState::AwaitingInput);
-        spawn_player(&mut ecs, map_builder.player_start);
-        spawn_amulet_of_yala(&mut ecs, map_builder.amulet_start);
-
-        Self {
+        build_level(&mut ecs, &mut resources, Depth(0))?;
+
+        Ok(Self {
             ecs,
             resources,
             input_systems: build_input_scheduler(),
             player_systems: build_player_scheduler(),
             monster_systems: build_monster_scheduler(),
-        }
+            levels: LevelStore::default(),
+        })
     }
 
     fn game_over(&mut self, ctx: &mut BTerm) {
@@ -126,19 +122,37 @@ impl State {
     fn reset_game_state(&mut self) {
         self.ecs = World::default();
         self.resources = Resources::default();
-        let mut rng = RandomNumberGenerator::new();
-        let map_builder = MapBuilder::new(&mut rng);
-        spawn_player(&mut self.ecs, map_builder.player_start);
-        spawn_amulet_of_yala(&mut self.ecs, map_builder.amulet_start);
-        map_builder
-            .rooms
-            .iter()
-            .skip(1)
-            .map(|r| r.center())
-            .for_each(|pos| spawn_monster(&mut self.ecs, &mut rng, pos));
-        self.resources.insert(map_builder.map);
-        self.resources.insert(Camera::new(map_builder.player_start));
-        self.resources.insert(TurnState::AwaitingInput);
+        self.levels = LevelStore::default();
+        build_level(&mut self.ecs, &mut self.resources, Depth(0))
+            .unwrap_or_else(|err| panic!("Map generation error: {err}"));
+    }
+
+    /// Takes the player up or down the stairs to `depth`. Only the player,
+    /// what they carry and their companions come along; the rest of the level
+    /// they leave is stored, ready for when they come back.
+    fn change_level(&mut self, depth: Depth) {
+        let current = *self.resources.get::<Depth>().unwrap();
+        let mut left_behind = World::default();
+        left_behind.move_from(
+            &mut self.ecs,
+            &(!component::<Player>() & !component::<Carried>() & !component::<Follower>()),
+        );
+        let map = self.resources.remove::<Map>().unwrap();
+        let theme = self.resources.remove::<Box<dyn MapTheme>>().unwrap();
+        self.levels.store(
+            current,
+            StoredLevel {
+                map,
+                theme,
+                world: left_behind,
+            },
+        );
+
+        match self.levels.take(depth) {
+            Some(level) => restore_level(&mut self.ecs, &mut self.resources, depth, level, current),
+            None => build_level(&mut self.ecs, &mut self.resources, depth)
+                .unwrap_or_else(|err| panic!("Map generation error: {err}")),
+        }
     }
 }
 
@@ -171,6 +185,14 @@ impl GameState for State {
                 .execute(&mut self.ecs, &mut self.resources),
             TurnState::GameOver => self.game_over(ctx),
             TurnState::Victory => self.victory(ctx),
+            TurnState::NextLevel => {
+                let depth = *self.resources.get::<Depth>().unwrap();
+                self.change_level(Depth(depth.0 + 1));
+            }
+            TurnState::PreviousLevel => {
+                let depth = *self.resources.get::<Depth>().unwrap();
+                self.change_level(Depth(depth.0 - 1));
+            }
         };
 
         render_draw_buffer(ctx).expect("Render error");
@@ -190,9 +212,9 @@ fn main() -> BError {
         // Layers 0, 1, 2
         .with_simple_console(DISPLAY_WIDTH, DISPLAY_HEIGHT, "dungeonfont.png")
         .with_simple_console_no_bg(DISPLAY_WIDTH, DISPLAY_HEIGHT, "dungeonfont.png")
-        .with_simple_console_no_bg(MAP_WIDTH * 2, MAP_HEIGHT * 2, "terminal8x8.png")
+        .with_simple_console_no_bg(SCREEN_WIDTH * 2, SCREEN_HEIGHT * 2, "terminal8x8.png")
         // Finish
         .build()?;
 
-    main_loop(context, State::new())
+    main_loop(context, State::new()?)
 }
@@ -1,81 +1,251 @@
 use crate::prelude::*;
 
-pub const NUM_TILES: usize = (MAP_WIDTH * MAP_HEIGHT) as usize;
+/// Extra pathing cost for stepping into a tile held by a blocking entity, so
+/// paths route around other creatures when there is a way past them.
+const OCCUPIED_TILE_COST: f32 = 8.0;
+
+/// Extra pathing cost for stepping onto a trap someone has spotted, so
+/// monsters go around it unless there is no other way.
+const KNOWN_TRAP_COST: f32 = 20.0;
+
+/// Extra pathing cost for a closed door, which takes a turn to open.
+const CLOSED_DOOR_COST: f32 = 1.0;
+
+/// Pathing cost of wading through shallow water, which is slow going.
+const SHALLOW_WATER_COST: f32 = 2.0;
+
+/// Pathing cost of swimming through deep water.
+const DEEP_WATER_COST: f32 = 3.0;
+
+/// Pathing cost of crossing lava, high enough that walkers only risk it when
+/// there is no other way.
+const LAVA_COST: f32 = 30.0;
 
 #[derive(Clone, Copy, PartialEq)]
 pub enum TileType {
     Wall,
     Floor,
     Space,
+    /// Stairs down to the next level.
+    Exit,
+    /// Stairs back up to the level above.
+    UpStairs,
+    /// Blocks movement and sight until someone bumps into it to open it.
+    DoorClosed,
+    DoorOpen,
+    /// Slows down anyone wading through it.
+    ShallowWater,
+    /// Only swimmers and flyers can cross it, and swimmers drop what they
+    /// carry.
+    DeepWater,
+    /// Burns anyone who walks into it.
+    Lava,
+    /// Walkers who step in fall through to the level below.
+    Chasm,
+    /// A door that passes for a wall, and acts like one, until someone
+    /// searching next to it finds it.
+    SecretDoor,
+}
+
+impl TileType {
+    /// Whether the tile is solid ground, where creatures and items can be
+    /// placed without harm.
+    pub fn is_walkable(self) -> bool {
+        matches!(
+            self,
+            TileType::Floor | TileType::Exit | TileType::UpStairs | TileType::DoorOpen
+        )
+    }
+
+    pub fn is_opaque(self) -> bool {
+        matches!(
+            self,
+            TileType::Wall | TileType::Space | TileType::DoorClosed | TileType::SecretDoor
+        )
+    }
+
+    /// What it costs a creature to move onto the tile, or `None` if it can't
+    /// cross the tile at all.
+    pub fn move_cost(self, mobility: Mobility) -> Option<f32> {
+        match self {
+            TileType::Wall | TileType::Space | TileType::SecretDoor => None,
+            TileType::DoorClosed => mobility.opens_doors.then_some(1.0 + CLOSED_DOOR_COST),
+            _ if mobility.flying || self.is_walkable() => Some(1.0),
+            TileType::ShallowWater => Some(SHALLOW_WATER_COST),
+            TileType::DeepWater => mobility.swimming.then_some(DEEP_WATER_COST),
+            TileType::Lava => Some(LAVA_COST),
+            _ => None,
+        }
+    }
 }
 
 pub struct Map {
+    /// Size of the map in tiles. Levels needn't all be the same size.
+    pub width: i32,
+    pub height: i32,
     pub tiles: Vec<TileType>,
     pub revealed: Vec<bool>,
+    pub occupied: Vec<bool>,
+    /// Lit tiles make it harder for the player to sneak past monsters.
+    pub lit: Vec<bool>,
+    /// Tiles holding a trap that has been revealed.
+    pub known_traps: Vec<bool>,
+    /// Bumped whenever a tile changes during play, so cached pathing data can
+    /// tell that it has gone stale.
+    pub revision: usize,
 }
 
 impl Map {
-    pub fn new() -> Self {
+    /// A map of the given size in tiles, all of them floor.
+    pub fn new(width: i32, height: i32) -> Self {
+        let num_tiles = usize::try_from(width * height).unwrap();
         Self {
-            tiles: vec![TileType::Floor; NUM_TILES],
-            revealed: vec![false; NUM_TILES],
+            width,
+            height,
+            tiles: vec![TileType::Floor; num_tiles],
+            revealed: vec![false; num_tiles],
+            occupied: vec![false; num_tiles],
+            lit: vec![false; num_tiles],
+            known_traps: vec![false; num_tiles],
+            revision: 0,
         }
     }
 
     /// Determine if player is within the map bounds.
     pub fn in_bounds(&self, point: Point) -> bool {
-        point.x >= 0 && point.x < MAP_WIDTH && point.y >= 0 && point.y < MAP_HEIGHT
+        point.x >= 0 && point.x < self.width && point.y >= 0 && point.y < self.height
     }
 
-    /// Determine if the player can enter the tile or is at the bounds of the
-    /// map.
+    /// Determine if the tile is in bounds and solid ground, somewhere any
+    /// creature can be put down safely. Whether a given creature may move
+    /// onto a tile is up to its `view` of the map.
     pub fn can_enter_tile(&self, point: Point) -> bool {
-        self.in_bounds(point) && self.tiles[map_idx(point.x, point.y)] == TileType::Floor
+        self.in_bounds(point) && self.tiles[self.point2d_to_index(point)].is_walkable()
     }
 
-    pub fn try_idx(&self, point: Point) -> Option<usize> {
-        if self.in_bounds(point) {
-            Some(map_idx(point.x, point.y))
-        } else {
-            None
+    /// Replaces a tile during play, such as when a door is opened, so that
+    /// cached pathing data knows to rebuild.
+    pub fn set_tile(&mut self, point: Point, tile: TileType) {
+        if let Some(idx) = self.try_idx(point) {
+            if self.tiles[idx] != tile {
+                self.tiles[idx] = tile;
+                self.revision += 1;
+            }
+        }
+    }
+
+    /// Whether the tile looks like a wall, secret doors included.
+    pub fn is_wall(&self, point: Point) -> bool {
+        self.try_idx(point)
+            .is_some_and(|idx| matches!(self.tiles[idx], TileType::Wall | TileType::SecretDoor))
+    }
+
+    /// Knocks down the wall at `point`, leaving floor, unless it is part of
+    /// the outer wall holding the level in. Returns whether it came down.
+    pub fn destroy_wall(&mut self, point: Point) -> bool {
+        let on_edge =
+            point.x <= 0 || point.y <= 0 || point.x >= self.width - 1 || point.y >= self.height - 1;
+        if on_edge || !self.is_wall(point) {
+            return false;
         }
+        self.set_tile(point, TileType::Floor);
+        self.tidy_walls_around(point);
+        true
     }
 
-    fn valid_exit(&self, loc: Point, delta: Point) -> Option<usize> {
-        let destination = loc + delta;
-        if self.in_bounds(destination) {
-            if self.can_enter_tile(destination) {
-                let idx = self.point2d_to_index(destination);
-                return Some(idx);
-            } else {
-                return None;
+    /// The map builder's wall clean-up, re-applied around a changed tile:
+    /// walls stand wherever they border open ground, and anything further
+    /// back is empty space.
+    fn tidy_walls_around(&mut self, point: Point) {
+        let is_open = |map: &Map, pos: Point| {
+            map.try_idx(pos)
+                .is_some_and(|idx| map.tiles[idx] != TileType::Space)
+                && !map.is_wall(pos)
+        };
+        for y in -1..=1 {
+            for x in -1..=1 {
+                let pos = point + Point::new(x, y);
+                let Some(idx) = self.try_idx(pos) else {
+                    continue;
+                };
+                let borders_open = (-1..=1)
+                    .flat_map(|y| (-1..=1).map(move |x| Point::new(x, y)))
+                    .any(|delta| delta != Point::zero() && is_open(self, pos + delta));
+                match self.tiles[idx] {
+                    TileType::Space if borders_open => self.set_tile(pos, TileType::Wall),
+                    TileType::Wall if !borders_open => self.set_tile(pos, TileType::Space),
+                    _ => {}
+                }
             }
-        } else {
-            return None;
         }
     }
-}
 
-impl BaseMap for Map {
-    fn get_available_exits(&self, idx: usize) -> SmallVec<[(usize, f32); 10]> {
-        let mut exits = SmallVec::new();
-        let location = self.index_to_point2d(idx);
+    /// The map as seen by a creature that gets about in the given way.
+    pub fn view(&self, mobility: Mobility) -> MapView<'_> {
+        MapView {
+            map: self,
+            mobility,
+        }
+    }
+
+    pub fn is_door_closed(&self, point: Point) -> bool {
+        self.try_idx(point)
+            .is_some_and(|idx| self.tiles[idx] == TileType::DoorClosed)
+    }
 
-        // Checking if Left, Right, Up, Down tiles are available exits.
-        if let Some(idx) = self.valid_exit(location, Point::new(-1, 0)) {
-            exits.push((idx, 1.0));
+    /// Determine if a blocking entity is standing on the tile.
+    pub fn is_occupied(&self, point: Point) -> bool {
+        self.try_idx(point).is_some_and(|idx| self.occupied[idx])
+    }
+
+    /// Mark a tile as held, or no longer held, by a blocking entity.
+    pub fn set_occupied(&mut self, point: Point, occupied: bool) {
+        if let Some(idx) = self.try_idx(point) {
+            self.occupied[idx] = occupied;
         }
-        if let Some(idx) = self.valid_exit(location, Point::new(1, 0)) {
-            exits.push((idx, 1.0));
+    }
+
+    pub fn clear_occupied(&mut self) {
+        self.occupied.iter_mut().for_each(|o| *o = false);
+    }
+
+    pub fn is_known_trap(&self, point: Point) -> bool {
+        self.try_idx(point).is_some_and(|idx| self.known_traps[idx])
+    }
+
+    /// Records where the revealed traps are. Paths steer around them, so the
+    /// revision is bumped whenever a new one turns up.
+    pub fn set_known_traps(&mut self, traps: impl Iterator<Item = Point>) {
+        let mut known_traps = vec![false; self.tiles.len()];
+        for pos in traps {
+            if let Some(idx) = self.try_idx(pos) {
+                known_traps[idx] = true;
+            }
         }
-        if let Some(idx) = self.valid_exit(location, Point::new(0, -1)) {
-            exits.push((idx, 1.0));
+        if known_traps != self.known_traps {
+            self.known_traps = known_traps;
+            self.revision += 1;
         }
-        if let Some(idx) = self.valid_exit(location, Point::new(0, 1)) {
-            exits.push((idx, 1.0));
+    }
+
+    pub fn is_lit(&self, point: Point) -> bool {
+        self.try_idx(point).is_some_and(|idx| self.lit[idx])
+    }
+
+    pub fn try_idx(&self, point: Point) -> Option<usize> {
+        if self.in_bounds(point) {
+            Some(self.point2d_to_index(point))
+        } else {
+            None
         }
+    }
+}
 
-        exits
+/// Creatures without a view of their own walk, so the map on its own paths
+/// the way a walker would.
+impl BaseMap for Map {
+    fn get_available_exits(&self, idx: usize) -> SmallVec<[(usize, f32); 10]> {
+        self.view(Mobility::default()).get_available_exits(idx)
     }
 
     fn get_pathing_distance(&self, idx1: usize, idx2: usize) -> f32 {
@@ -83,13 +253,13 @@ impl BaseMap for Map {
     }
 
     fn is_opaque(&self, idx: usize) -> bool {
-        self.tiles[idx as usize] != TileType::Floor
+        self.tiles[idx].is_opaque()
     }
 }
 
 impl Algorithm2D for Map {
     fn dimensions(&self) -> Point {
-        Point::new(MAP_WIDTH, MAP_HEIGHT)
+        Point::new(self.width, self.height)
     }
 
     fn in_bounds(&self, pos: Point) -> bool {
@@ -97,13 +267,130 @@ impl Algorithm2D for Map {
     }
 }
 
-pub fn map_idx(x: i32, y: i32) -> usize {
-    ((y * MAP_WIDTH) + x) as usize
+/// The map as a particular creature sees it when working out where it can
+/// go, so pathing follows the creature's own movement rules.
+pub struct MapView<'a> {
+    map: &'a Map,
+    mobility: Mobility,
+}
+
+impl MapView<'_> {
+    /// Determine if the creature can move onto the tile. Anyone may step off
+    /// the edge of a chasm, but only flyers will plan a path over one.
+    pub fn can_enter_tile(&self, point: Point) -> bool {
+        self.map.try_idx(point).is_some_and(|idx| {
+            let tile = self.map.tiles[idx];
+            tile != TileType::DoorClosed
+                && (tile.move_cost(self.mobility).is_some() || tile == TileType::Chasm)
+        })
+    }
+}
+
+impl BaseMap for MapView<'_> {
+    fn get_available_exits(&self, idx: usize) -> SmallVec<[(usize, f32); 10]> {
+        let mut exits = SmallVec::new();
+        let location = self.map.index_to_point2d(idx);
+
+        // Checking if Left, Right, Up, Down tiles are available exits. Paths
+        // may lead through closed doors, which are opened on the way by
+        // anyone able to.
+        [
+            Point::new(-1, 0),
+            Point::new(1, 0),
+            Point::new(0, -1),
+            Point::new(0, 1),
+        ]
+        .iter()
+        .filter_map(|delta| self.map.try_idx(location + *delta))
+        .for_each(|idx| {
+            let Some(mut cost) = self.map.tiles[idx].move_cost(self.mobility) else {
+                return;
+            };
+            // Occupied tiles stay walkable for pathing, just expensive.
+            if self.map.occupied[idx] {
+                cost += OCCUPIED_TILE_COST;
+            }
+            if self.map.known_traps[idx] {
+                cost += KNOWN_TRAP_COST;
+            }
+            exits.push((idx, cost));
+        });
+
+        exits
+    }
+
+    fn get_pathing_distance(&self, idx1: usize, idx2: usize) -> f32 {
+        self.map.get_pathing_distance(idx1, idx2)
+    }
+
+    fn is_opaque(&self, idx: usize) -> bool {
+        self.map.is_opaque(idx)
+    }
+}
+
+impl Algorithm2D for MapView<'_> {
+    fn dimensions(&self) -> Point {
+        self.map.dimensions()
+    }
+
+    fn in_bounds(&self, pos: Point) -> bool {
+        self.map.in_bounds(pos)
+    }
 }
 
-pub fn convert_idx_to_point(idx: usize) -> Point {
-    let i32_idx = idx as i32;
-    let x = i32_idx % MAP_WIDTH; // remainder, loops after reaching map width.
-    let y = i32_idx / MAP_WIDTH; // result always an integer rounded down.
-    Point::new(x, y)
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WALKER: Mobility = Mobility {
+        flying: false,
+        swimming: false,
+        opens_doors: true,
+    };
+
+    /// What it costs a creature to step from `from` to `to`, if it can at all.
+    fn step_cost(map: &Map, mobility: Mobility, from: Point, to: Point) -> Option<f32> {
+        let to = map.point2d_to_index(to);
+        map.view(mobility)
+            .get_available_exits(map.point2d_to_index(from))
+            .into_iter()
+            .find(|(idx, _)| *idx == to)
+            .map(|(_, cost)| cost)
+    }
+
+    #[test]
+    fn only_creatures_that_open_doors_path_through_them() {
+        let mut map = Map::new(5, 5);
+        let door = Point::new(2, 2);
+        map.set_tile(door, TileType::DoorClosed);
+        let beast = Mobility {
+            opens_doors: false,
+            ..WALKER
+        };
+
+        assert_eq!(
+            step_cost(&map, WALKER, Point::new(1, 2), door),
+            Some(1.0 + CLOSED_DOOR_COST)
+        );
+        assert_eq!(step_cost(&map, beast, Point::new(1, 2), door), None);
+    }
+
+    #[test]
+    fn paths_steer_around_known_traps() {
+        let mut map = Map::new(5, 5);
+        let trap = Point::new(2, 2);
+        assert_eq!(step_cost(&map, WALKER, Point::new(1, 2), trap), Some(1.0));
+
+        map.set_known_traps(std::iter::once(trap));
+        assert_eq!(
+            step_cost(&map, WALKER, Point::new(1, 2), trap),
+            Some(1.0 + KNOWN_TRAP_COST)
+        );
+    }
+
+    #[test]
+    fn secret_doors_block_sight_like_walls() {
+        assert!(TileType::SecretDoor.is_opaque());
+        assert!(!TileType::DoorOpen.is_opaque());
+    }
 }
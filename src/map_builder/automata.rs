@@ -0,0 +1,135 @@
+use super::MapArchitect;
+use crate::prelude::*;
+
+const SMOOTHING_ITERATIONS: usize = 10;
+
+/// Organic caverns: random noise smoothed into caves by a cellular automaton.
+pub struct CellularAutomataArchitect {}
+
+impl MapArchitect for CellularAutomataArchitect {
+    fn build(&mut self, rng: &mut RandomNumberGenerator, width: i32, height: i32) -> MapBuilder {
+        let mut mb = MapBuilder::empty(width, height);
+        random_noise_map(rng, &mut mb.map);
+        for _ in 0..SMOOTHING_ITERATIONS {
+            iteration(&mut mb.map);
+        }
+        add_boundaries(&mut mb.map);
+        mb.player_start = keep_largest_cave(&mut mb.map);
+        mb.light_open_areas();
+        mb.amulet_start = mb.find_most_distant();
+        mb.monster_spawns = mb.random_monster_spawns(rng);
+        mb
+    }
+}
+
+/// Seed the map with a little more floor than wall.
+fn random_noise_map(rng: &mut RandomNumberGenerator, map: &mut Map) {
+    map.tiles.iter_mut().for_each(|t| {
+        let roll = rng.range(0, 100);
+        if roll > 55 {
+            *t = TileType::Floor;
+        } else {
+            *t = TileType::Wall;
+        }
+    });
+}
+
+fn count_neighbors(x: i32, y: i32, map: &Map) -> usize {
+    let mut neighbors = 0;
+    for iy in -1..=1 {
+        for ix in -1..=1 {
+            let idx = map.point2d_to_index(Point::new(x + ix, y + iy));
+            if !(ix == 0 && iy == 0) && map.tiles[idx] == TileType::Wall {
+                neighbors += 1;
+            }
+        }
+    }
+    neighbors
+}
+
+/// One smoothing pass: crowded tiles fill in as wall, and lone floor tiles
+/// open up into caves.
+fn iteration(map: &mut Map) {
+    let mut new_tiles = map.tiles.clone();
+    for y in 1..map.height - 1 {
+        for x in 1..map.width - 1 {
+            let neighbors = count_neighbors(x, y, map);
+            let idx = map.point2d_to_index(Point::new(x, y));
+            if neighbors > 4 || neighbors == 0 {
+                new_tiles[idx] = TileType::Wall;
+            } else {
+                new_tiles[idx] = TileType::Floor;
+            }
+        }
+    }
+    map.tiles = new_tiles;
+}
+
+/// Wall in the edges of the map so nothing can walk off it.
+pub(super) fn add_boundaries(map: &mut Map) {
+    for idx in 0..map.tiles.len() {
+        let pos = map.index_to_point2d(idx);
+        if pos.x == 0 || pos.y == 0 || pos.x == map.width - 1 || pos.y == map.height - 1 {
+            map.tiles[idx] = TileType::Wall;
+        }
+    }
+}
+
+/// Every separate area of floor on the map, as lists of tile indices.
+pub(super) fn find_caves(map: &Map) -> Vec<Vec<usize>> {
+    const UNREACHABLE: f32 = f32::MAX;
+
+    // Measure each cave by flooding it with a Dijkstra map from one of its
+    // tiles.
+    let mut visited = vec![false; map.tiles.len()];
+    let mut caves = Vec::new();
+    for idx in 0..map.tiles.len() {
+        if visited[idx] || map.tiles[idx] != TileType::Floor {
+            continue;
+        }
+        let dijkstra_map = DijkstraMap::new(map.width, map.height, &[idx], map, 1024.0);
+        let cave: Vec<usize> = dijkstra_map
+            .map
+            .iter()
+            .enumerate()
+            .filter(|(_, dist)| **dist < UNREACHABLE)
+            .map(|(idx, _)| idx)
+            .collect();
+        for idx in &cave {
+            visited[*idx] = true;
+        }
+        caves.push(cave);
+    }
+    caves
+}
+
+/// Walls off every cave except the largest connected one, so the whole map is
+/// reachable, and returns the floor tile of that cave closest to the center of
+/// the map for the player to start on.
+pub(super) fn keep_largest_cave(map: &mut Map) -> Point {
+    let largest = find_caves(map)
+        .into_iter()
+        .max_by_key(Vec::len)
+        .unwrap_or_default();
+
+    let mut in_largest = vec![false; map.tiles.len()];
+    for idx in &largest {
+        in_largest[*idx] = true;
+    }
+    map.tiles
+        .iter_mut()
+        .enumerate()
+        .filter(|(idx, _)| !in_largest[*idx])
+        .for_each(|(_, tile)| *tile = TileType::Wall);
+
+    let center = Point::new(map.width / 2, map.height / 2);
+    largest
+        .iter()
+        .map(|idx| map.index_to_point2d(*idx))
+        .min_by(|a, b| {
+            let distance_a = DistanceAlg::Pythagoras.distance2d(center, *a);
+            let distance_b = DistanceAlg::Pythagoras.distance2d(center, *b);
+            distance_a.partial_cmp(&distance_b).unwrap()
+        })
+        .unwrap_or(center)
+}
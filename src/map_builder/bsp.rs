@@ -0,0 +1,94 @@
+use super::MapArchitect;
+use crate::prelude::*;
+
+/// Leaves smaller than this in either direction are not split any further.
+const MIN_LEAF_SIZE: i32 = 8;
+/// Splitting stops after this many levels, giving at most 2^depth rooms.
+const MAX_DEPTH: usize = 4;
+const MIN_ROOM_SIZE: i32 = 3;
+
+/// Rooms laid out by binary space partitioning: the map is split in two over
+/// and over, each leaf gets a room, and sibling leaves are joined by
+/// corridors. Rooms never overlap or leave the map, and walking the tree
+/// always connects every room.
+pub struct BspArchitect {}
+
+impl MapArchitect for BspArchitect {
+    fn build(&mut self, rng: &mut RandomNumberGenerator, width: i32, height: i32) -> MapBuilder {
+        let mut mb = MapBuilder::empty(width, height);
+        mb.fill(TileType::Wall);
+        let whole_map = Rect::with_size(0, 0, width, height);
+        partition(&mut mb, rng, whole_map, 0);
+
+        mb.player_start = mb.rooms[0].center();
+        mb.amulet_start = mb.find_most_distant();
+        mb.monster_spawns = mb.rooms.iter().skip(1).map(Rect::center).collect();
+        mb
+    }
+}
+
+/// Splits `area` in two and partitions each half, or places a room if the
+/// area is too small to split. Returns a point inside the rooms built, so
+/// the parent can tunnel between its two halves.
+fn partition(
+    mb: &mut MapBuilder,
+    rng: &mut RandomNumberGenerator,
+    area: Rect,
+    depth: usize,
+) -> Point {
+    let can_split_x = area.width() >= MIN_LEAF_SIZE * 2;
+    let can_split_y = area.height() >= MIN_LEAF_SIZE * 2;
+    if depth >= MAX_DEPTH || !(can_split_x || can_split_y) {
+        return place_room(mb, rng, area);
+    }
+
+    // Split across the longer side, so leaves stay roughly square.
+    let split_x = if can_split_x && can_split_y {
+        area.width() > area.height()
+    } else {
+        can_split_x
+    };
+    let (first, second) = if split_x {
+        let split = rng.range(area.x1 + MIN_LEAF_SIZE, area.x2 - MIN_LEAF_SIZE + 1);
+        (
+            Rect::with_exact(area.x1, area.y1, split, area.y2),
+            Rect::with_exact(split, area.y1, area.x2, area.y2),
+        )
+    } else {
+        let split = rng.range(area.y1 + MIN_LEAF_SIZE, area.y2 - MIN_LEAF_SIZE + 1);
+        (
+            Rect::with_exact(area.x1, area.y1, area.x2, split),
+            Rect::with_exact(area.x1, split, area.x2, area.y2),
+        )
+    };
+
+    let first = partition(mb, rng, first, depth + 1);
+    let second = partition(mb, rng, second, depth + 1);
+    if rng.range(0, 2) == 1 {
+        mb.apply_horizontal_tunnel(first.x, second.x, first.y);
+        mb.apply_vertical_tunnel(first.y, second.y, second.x);
+    } else {
+        mb.apply_vertical_tunnel(first.y, second.y, first.x);
+        mb.apply_horizontal_tunnel(first.x, second.x, second.y);
+    }
+    first
+}
+
+/// Carves a randomly sized room inside a leaf, keeping a wall between it and
+/// the edge of the leaf.
+fn place_room(mb: &mut MapBuilder, rng: &mut RandomNumberGenerator, leaf: Rect) -> Point {
+    let width = rng.range(MIN_ROOM_SIZE, leaf.width() - 1);
+    let height = rng.range(MIN_ROOM_SIZE, leaf.height() - 1);
+    let x = rng.range(leaf.x1 + 1, leaf.x2 - width);
+    let y = rng.range(leaf.y1 + 1, leaf.y2 - height);
+    let room = Rect::with_size(x, y, width, height);
+
+    // Rooms are lit while corridors are dark.
+    room.for_each(|point| {
+        let idx = mb.map.point2d_to_index(point);
+        mb.map.tiles[idx] = TileType::Floor;
+        mb.map.lit[idx] = true;
+    });
+    mb.rooms.push(room);
+    room.center()
+}
@@ -0,0 +1,89 @@
+use super::MapArchitect;
+use crate::prelude::*;
+
+/// How many steps a digger takes before it wanders off.
+const STAGGER_DISTANCE: usize = 400;
+/// Percentage of the map dug out before the architect stops.
+const DESIRED_FLOOR_PERCENT: usize = 33;
+/// Tunnels further than this from the player start are filled back in.
+const MAX_REACH: f32 = 2000.0;
+
+/// Winding tunnels dug by drunken miners stumbling around the map.
+pub struct DrunkardsWalkArchitect {}
+
+impl MapArchitect for DrunkardsWalkArchitect {
+    fn build(&mut self, rng: &mut RandomNumberGenerator, width: i32, height: i32) -> MapBuilder {
+        let mut mb = MapBuilder::empty(width, height);
+        mb.fill(TileType::Wall);
+        let center = Point::new(width / 2, height / 2);
+        drunkard(center, rng, &mut mb.map);
+
+        let desired_floor = mb.map.tiles.len() * DESIRED_FLOOR_PERCENT / 100;
+        while floor_count(&mb.map) < desired_floor {
+            // New diggers set off from somewhere already dug, so every tunnel
+            // joins up with the rest.
+            let dug: Vec<usize> = floor_tiles(&mb.map).collect();
+            let start = mb
+                .map
+                .index_to_point2d(dug[rng.random_slice_index(&dug).unwrap()]);
+            drunkard(start, rng, &mut mb.map);
+            cull_unreachable(&mut mb.map, center);
+        }
+
+        mb.player_start = center;
+        mb.light_open_areas();
+        mb.amulet_start = mb.find_most_distant();
+        mb.monster_spawns = mb.random_monster_spawns(rng);
+        mb
+    }
+}
+
+/// Staggers about from `start`, digging out every tile it steps on. The edges
+/// of the map are never dug so it stays walled in.
+fn drunkard(start: Point, rng: &mut RandomNumberGenerator, map: &mut Map) {
+    let mut drunkard_pos = start;
+    for _ in 0..STAGGER_DISTANCE {
+        let idx = map.point2d_to_index(drunkard_pos);
+        map.tiles[idx] = TileType::Floor;
+
+        let next = match rng.range(0, 4) {
+            0 => drunkard_pos + Point::new(-1, 0),
+            1 => drunkard_pos + Point::new(1, 0),
+            2 => drunkard_pos + Point::new(0, -1),
+            _ => drunkard_pos + Point::new(0, 1),
+        };
+        drunkard_pos = Point::new(
+            next.x.clamp(1, map.width - 2),
+            next.y.clamp(1, map.height - 2),
+        );
+    }
+}
+
+fn floor_tiles(map: &Map) -> impl Iterator<Item = usize> + '_ {
+    map.tiles
+        .iter()
+        .enumerate()
+        .filter(|(_, tile)| **tile == TileType::Floor)
+        .map(|(idx, _)| idx)
+}
+
+fn floor_count(map: &Map) -> usize {
+    floor_tiles(map).count()
+}
+
+/// Fills in any tunnel the player could not reach from `start`.
+fn cull_unreachable(map: &mut Map, start: Point) {
+    let dijkstra_map = DijkstraMap::new(
+        map.width,
+        map.height,
+        &[map.point2d_to_index(start)],
+        map,
+        MAX_REACH,
+    );
+    dijkstra_map
+        .map
+        .iter()
+        .enumerate()
+        .filter(|(_, distance)| **distance > MAX_REACH)
+        .for_each(|(idx, _)| map.tiles[idx] = TileType::Wall);
+}
@@ -0,0 +1,406 @@
+use crate::prelude::*;
+
+mod automata;
+mod bsp;
+mod drunkard;
+mod rooms;
+mod terrain;
+mod themes;
+mod validation;
+mod vaults;
+mod wfc;
+
+use automata::CellularAutomataArchitect;
+use bsp::BspArchitect;
+use drunkard::DrunkardsWalkArchitect;
+use rooms::RoomsArchitect;
+pub use themes::*;
+pub use validation::MapGenError;
+use validation::MIN_MAP_SIZE;
+use wfc::{WaveFunctionCollapseArchitect, SAMPLE_DUNGEON};
+
+const NUM_MONSTERS: usize = 20;
+const NUM_TRAPS: usize = 6;
+/// Levels generated before giving up on getting a playable one.
+const MAX_ATTEMPTS: usize = 10;
+
+/// Map generators. Each architect lays out the tiles of a fresh map of the
+/// size asked for and picks where the player and the amulet start;
+/// `MapBuilder::with_architect` takes care of the vaults and clean-up passes
+/// shared by every architect.
+pub trait MapArchitect {
+    fn build(&mut self, rng: &mut RandomNumberGenerator, width: i32, height: i32) -> MapBuilder;
+}
+
+/// Names accepted by `architect_by_name`.
+pub const ARCHITECT_NAMES: [&str; 5] = ["rooms", "bsp", "caves", "drunkard", "wfc"];
+
+/// Looks up an architect by name, as used by the `DUNGEON_ARCHITECT`
+/// environment variable.
+pub fn architect_by_name(name: &str) -> Option<Box<dyn MapArchitect>> {
+    match name {
+        "rooms" => Some(Box::new(RoomsArchitect {})),
+        "bsp" => Some(Box::new(BspArchitect {})),
+        "caves" => Some(Box::new(CellularAutomataArchitect {})),
+        "drunkard" => Some(Box::new(DrunkardsWalkArchitect {})),
+        "wfc" => Some(Box::new(WaveFunctionCollapseArchitect::from_sample(
+            SAMPLE_DUNGEON,
+        ))),
+        _ => None,
+    }
+}
+
+pub struct MapBuilder {
+    pub map: Map,
+    pub rooms: Vec<Rect>,
+    pub monster_spawns: Vec<Point>,
+    /// Monsters placed by a vault, spawned one to a tile rather than as
+    /// encounters.
+    pub vault_monsters: Vec<Point>,
+    pub item_spawns: Vec<Point>,
+    pub trap_spawns: Vec<Point>,
+    pub player_start: Point,
+    pub amulet_start: Point,
+    pub boss_start: Point,
+    pub companion_start: Point,
+    pub theme: Box<dyn MapTheme>,
+}
+
+impl MapBuilder {
+    /// Builds a level of the given size in tiles with the architect named by
+    /// the `DUNGEON_ARCHITECT` environment variable, or with one picked at
+    /// random. A level that fails validation is thrown away and another built
+    /// from a new seed; if none of them pass, the last failure is returned.
+    pub fn new(
+        rng: &mut RandomNumberGenerator,
+        width: i32,
+        height: i32,
+    ) -> Result<Self, MapGenError> {
+        match std::env::var("DUNGEON_ARCHITECT") {
+            Ok(name) => Self::with_named_architect(rng, &name, width, height),
+            Err(_) => Self::with_retries(rng, width, height, Self::random_architect),
+        }
+    }
+
+    /// Builds a level with the architect called `name`, retrying like `new`.
+    /// A name that isn't one of `ARCHITECT_NAMES` is an error rather than a
+    /// silent fall back to random architects.
+    pub fn with_named_architect(
+        rng: &mut RandomNumberGenerator,
+        name: &str,
+        width: i32,
+        height: i32,
+    ) -> Result<Self, MapGenError> {
+        if !ARCHITECT_NAMES.contains(&name) {
+            return Err(MapGenError::UnknownArchitect(name.to_string()));
+        }
+        Self::with_retries(rng, width, height, |_| {
+            architect_by_name(name).expect("listed in ARCHITECT_NAMES")
+        })
+    }
+
+    /// Builds levels with the architects `pick` hands out, each from a fresh
+    /// seed, until one passes validation or `MAX_ATTEMPTS` have failed.
+    fn with_retries(
+        rng: &mut RandomNumberGenerator,
+        width: i32,
+        height: i32,
+        mut pick: impl FnMut(&mut RandomNumberGenerator) -> Box<dyn MapArchitect>,
+    ) -> Result<Self, MapGenError> {
+        let mut attempts = 1;
+        loop {
+            let mut attempt_rng = RandomNumberGenerator::seeded(rng.next_u64());
+            let mut architect = pick(&mut attempt_rng);
+            match Self::with_architect(&mut attempt_rng, architect.as_mut(), width, height) {
+                Err(_) if attempts < MAX_ATTEMPTS => attempts += 1,
+                result => return result,
+            }
+        }
+    }
+
+    /// Builds a level with a specific architect, such as when trying out a new
+    /// generator, and checks that it is playable.
+    pub fn with_architect(
+        rng: &mut RandomNumberGenerator,
+        architect: &mut dyn MapArchitect,
+        width: i32,
+        height: i32,
+    ) -> Result<Self, MapGenError> {
+        if width < MIN_MAP_SIZE || height < MIN_MAP_SIZE {
+            return Err(MapGenError::TooSmall { width, height });
+        }
+        let mut mb = architect.build(rng, width, height);
+        vaults::stamp_random_vault(&mut mb, rng);
+        mb.remove_extra_walls();
+        mb.boss_start = mb.free_tile_beside(mb.amulet_start);
+        mb.companion_start = mb.free_tile_beside(mb.player_start);
+        terrain::add_pools(&mut mb, rng);
+        mb.trap_spawns = mb.random_trap_spawns(rng);
+        mb.theme = random_theme(rng);
+        validation::validate(&mb)?;
+        Ok(mb)
+    }
+
+    /// Opens a chasm onto the level below. Left to the caller, since the
+    /// bottom level has nowhere to fall to.
+    pub fn add_chasm(&mut self, rng: &mut RandomNumberGenerator) {
+        terrain::add_chasm(self, rng);
+    }
+
+    fn random_architect(rng: &mut RandomNumberGenerator) -> Box<dyn MapArchitect> {
+        let mut architects: Vec<Box<dyn MapArchitect>> = vec![
+            Box::new(RoomsArchitect {}),
+            Box::new(BspArchitect {}),
+            Box::new(CellularAutomataArchitect {}),
+            Box::new(DrunkardsWalkArchitect {}),
+            Box::new(WaveFunctionCollapseArchitect::from_sample(SAMPLE_DUNGEON)),
+        ];
+        let choice = rng.range(0, architects.len());
+        architects.swap_remove(choice)
+    }
+
+    /// A map of the given size filled with nothing but floor, for architects
+    /// to start from.
+    pub fn empty(width: i32, height: i32) -> Self {
+        MapBuilder {
+            map: Map::new(width, height),
+            rooms: Vec::new(),
+            monster_spawns: Vec::new(),
+            vault_monsters: Vec::new(),
+            item_spawns: Vec::new(),
+            trap_spawns: Vec::new(),
+            player_start: Point::zero(),
+            amulet_start: Point::zero(),
+            boss_start: Point::zero(),
+            companion_start: Point::zero(),
+            theme: Box::new(CryptTheme {}),
+        }
+    }
+
+    /// Finds the reachable tile furthest from the player start, where the
+    /// amulet is placed. A player start boxed in on every side leaves the
+    /// amulet at the start, for validation to reject.
+    fn find_most_distant(&self) -> Point {
+        // Create dijkstra_map from player_start
+        let dijkstra_map = DijkstraMap::new(
+            self.map.width,
+            self.map.height,
+            &[self.map.point2d_to_index(self.player_start)],
+            &self.map,
+            1024.0,
+        );
+
+        const UNREACHABLE: &f32 = &f32::MAX;
+        dijkstra_map
+            .map
+            .iter()
+            .enumerate()
+            .filter(|(idx, dist)| *dist < UNREACHABLE && self.map.tiles[*idx].is_walkable())
+            .max_by(|a, b| a.1.partial_cmp(b.1).unwrap())
+            .map_or(self.player_start, |(idx, _)| self.map.index_to_point2d(idx))
+    }
+
+    /// Picks random floor tiles well away from the player start for monsters,
+    /// for maps that have no rooms to put them in.
+    fn random_monster_spawns(&self, rng: &mut RandomNumberGenerator) -> Vec<Point> {
+        self.random_distant_floor(rng, NUM_MONSTERS)
+    }
+
+    /// Scatters traps over the level, keeping them off the tiles where
+    /// anything else is placed and out of corners the player can't get to.
+    fn random_trap_spawns(&self, rng: &mut RandomNumberGenerator) -> Vec<Point> {
+        let taken = [self.amulet_start, self.boss_start, self.companion_start];
+        let reachable = terrain::safely_reachable(self, true);
+        let mut spawns = self.random_distant_floor(rng, NUM_TRAPS);
+        spawns.retain(|pos| {
+            reachable[self.map.point2d_to_index(*pos)]
+                && !taken.contains(pos)
+                && !self.monster_spawns.contains(pos)
+                && !self.vault_monsters.contains(pos)
+                && !self.item_spawns.contains(pos)
+        });
+        spawns
+    }
+
+    /// Up to `count` random floor tiles more than ten tiles from the player
+    /// start.
+    fn random_distant_floor(&self, rng: &mut RandomNumberGenerator, count: usize) -> Vec<Point> {
+        let mut spawnable_tiles: Vec<Point> = self
+            .map
+            .tiles
+            .iter()
+            .enumerate()
+            .filter(|(idx, tile)| {
+                **tile == TileType::Floor
+                    && DistanceAlg::Pythagoras
+                        .distance2d(self.player_start, self.map.index_to_point2d(*idx))
+                        > 10.0
+            })
+            .map(|(idx, _)| self.map.index_to_point2d(idx))
+            .collect();
+
+        let mut spawns = Vec::new();
+        while spawns.len() < count && !spawnable_tiles.is_empty() {
+            let target_index = rng.random_slice_index(&spawnable_tiles).unwrap();
+            spawns.push(spawnable_tiles.swap_remove(target_index));
+        }
+        spawns
+    }
+
+    /// Finds a floor tile next to `pos` for someone to stand on, such as the
+    /// boss guarding the amulet, or `pos` itself if it is boxed in. The player
+    /// start and monster spawn points are avoided since entities spawn there.
+    fn free_tile_beside(&self, pos: Point) -> Point {
+        [
+            Point::new(-1, 0),
+            Point::new(1, 0),
+            Point::new(0, -1),
+            Point::new(0, 1),
+        ]
+        .iter()
+        .map(|delta| pos + *delta)
+        .find(|pos| {
+            self.map.can_enter_tile(*pos)
+                && *pos != self.player_start
+                && !self.monster_spawns.contains(pos)
+                && !self.vault_monsters.contains(pos)
+        })
+        .unwrap_or(pos)
+    }
+
+    /// Fill in every tile on the map.
+    fn fill(&mut self, tile: TileType) {
+        self.map.tiles.iter_mut().for_each(|t| *t = tile);
+    }
+
+    /// Lights the open stretches of floor, for architects without rooms to
+    /// light: every tile within a step of a tile surrounded by floor. Narrow
+    /// passages, with a wall beside every tile, stay dark.
+    fn light_open_areas(&mut self) {
+        let neighbourhood = |pos: Point| {
+            (-1..=1).flat_map(move |dy| (-1..=1).map(move |dx| pos + Point::new(dx, dy)))
+        };
+        let is_floor = |pos: Point| {
+            self.map
+                .try_idx(pos)
+                .is_some_and(|idx| self.map.tiles[idx] == TileType::Floor)
+        };
+        let lit: Vec<usize> = (0..self.map.tiles.len())
+            .filter(|idx| {
+                let pos = self.map.index_to_point2d(*idx);
+                is_floor(pos) && neighbourhood(pos).any(|open| neighbourhood(open).all(is_floor))
+            })
+            .collect();
+        for idx in lit {
+            self.map.lit[idx] = true;
+        }
+    }
+
+    fn apply_vertical_tunnel(&mut self, y1: i32, y2: i32, x: i32) {
+        use std::cmp::{max, min};
+        for y in min(y1, y2)..=max(y1, y2) {
+            let point = Point::new(x, y);
+            if let Some(idx) = self.map.try_idx(point) {
+                self.map.tiles[idx] = TileType::Floor;
+            }
+        }
+    }
+
+    fn apply_horizontal_tunnel(&mut self, x1: i32, x2: i32, y: i32) {
+        use std::cmp::{max, min};
+        for x in min(x1, x2)..=max(x1, x2) {
+            let point = Point::new(x, y);
+            if let Some(idx) = self.map.try_idx(point) {
+                self.map.tiles[idx] = TileType::Floor;
+            }
+        }
+    }
+
+    /**
+     * If a wall tile has no adjacent floor tiles, replace it with a space tile.
+     */
+    fn remove_extra_walls(&mut self) {
+        let mut space_tile_index: Vec<usize> = vec![];
+
+        let neighboring_offsets: [(&str, Point); 8] = [
+            ("top", Point::new(0, -1)),
+            ("top_right", Point::new(1, -1)),
+            ("right", Point::new(1, 0)),
+            ("bottom_right", Point::new(1, 1)),
+            ("bottom", Point::new(0, 1)),
+            ("buttom_left", Point::new(-1, 1)),
+            ("left", Point::new(-1, 0)),
+            ("top_left", Point::new(-1, -1)),
+        ];
+
+        self.map.tiles.iter().enumerate().for_each(|(index, tile)| {
+            let map_point = self.map.index_to_point2d(index);
+
+            if *tile == TileType::Wall {
+                let mut neighbor_is_floor: Vec<bool> = vec![];
+                // check if any neighbors are Floor tiles.
+                neighboring_offsets.iter().for_each(|(_, offset)| {
+                    let neighboring_point = map_point + *offset;
+                    // if index == 400 {
+
+                    //     println!("index {:?}", index);
+                    //     println!("map_point {:?}", map_point);
+                    //     println!("offset {:?}", offset);
+                    //     println!("neighboring_point {:?}", neighboring_point);
+                    // }
+                    if let Some(idx) = self.map.try_idx(neighboring_point) {
+                        if self.map.tiles[idx] == TileType::Floor {
+                            neighbor_is_floor.push(true);
+                        }
+                    }
+                    // if there are no surrounding floors, add index to list.
+                });
+
+                if neighbor_is_floor.len() == 0 {
+                    space_tile_index.push(index);
+                }
+            }
+        });
+        // Add space tiles.
+        space_tile_index.iter().for_each(|index| {
+            self.map.tiles[*index] = TileType::Space;
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_architect_builds_small_levels() {
+        for name in ARCHITECT_NAMES {
+            for size in [MIN_MAP_SIZE, 12, 20] {
+                let mut rng = RandomNumberGenerator::seeded(1);
+                let result = MapBuilder::with_named_architect(&mut rng, name, size, size);
+                assert!(result.is_ok(), "{name} at {size}: {:?}", result.err());
+            }
+        }
+    }
+
+    #[test]
+    fn rejects_levels_too_small_to_lay_out() {
+        let mut rng = RandomNumberGenerator::seeded(1);
+        assert_eq!(
+            MapBuilder::new(&mut rng, 4, 20).err(),
+            Some(MapGenError::TooSmall {
+                width: 4,
+                height: 20
+            })
+        );
+    }
+
+    #[test]
+    fn rejects_unknown_architect_names() {
+        let mut rng = RandomNumberGenerator::seeded(1);
+        assert_eq!(
+            MapBuilder::with_named_architect(&mut rng, "cavse", 40, 20).err(),
+            Some(MapGenError::UnknownArchitect("cavse".to_string()))
+        );
+    }
+}
@@ -0,0 +1,176 @@
+use super::MapArchitect;
+use crate::prelude::*;
+
+/// One room for every this many tiles of map, so bigger maps get more rooms.
+const TILES_PER_ROOM: usize = 200;
+/// Rooms tried before settling for however many fitted.
+const MAX_ROOM_ATTEMPTS: usize = 1000;
+const NUM_SECRET_DOORS: usize = 3;
+/// Rooms that may be left reachable only through secret doors.
+const MAX_SECRET_ROOMS: usize = 2;
+
+/// The classic layout: rectangular rooms joined by L-shaped corridors.
+pub struct RoomsArchitect {}
+
+impl MapArchitect for RoomsArchitect {
+    fn build(&mut self, rng: &mut RandomNumberGenerator, width: i32, height: i32) -> MapBuilder {
+        let mut mb = MapBuilder::empty(width, height);
+        mb.fill(TileType::Wall);
+        build_random_rooms(&mut mb, rng);
+        build_corridors(&mut mb, rng);
+        // place player in the center of the first room.
+        mb.player_start = mb.rooms[0].center();
+        let secret_rooms = hide_doors(&mut mb, rng);
+        // Find a far point to position the amulet. Secret doors stay shut
+        // to pathing, so it is never hidden behind one.
+        mb.amulet_start = mb.find_most_distant();
+        // One encounter per room, except the first room for the starting
+        // player. Secret rooms hold treasure instead.
+        mb.monster_spawns = mb
+            .rooms
+            .iter()
+            .skip(1)
+            .map(Rect::center)
+            .filter(|center| !secret_rooms.contains(center))
+            .collect();
+        mb.item_spawns = secret_rooms;
+        mb
+    }
+}
+
+fn build_random_rooms(mb: &mut MapBuilder, rng: &mut RandomNumberGenerator) {
+    let num_rooms = (mb.map.tiles.len() / TILES_PER_ROOM).max(1);
+    for _ in 0..MAX_ROOM_ATTEMPTS {
+        if mb.rooms.len() >= num_rooms {
+            break;
+        }
+        // Rooms stay clear of the map edge, however small the map.
+        let width = rng.range(2, 10).min(mb.map.width - 2);
+        let height = rng.range(2, 10).min(mb.map.height - 2);
+        let room = Rect::with_size(
+            rng.range(1, mb.map.width - width),
+            rng.range(1, mb.map.height - height),
+            width,
+            height,
+        );
+
+        let mut overlap = false;
+        // Check if any existing rooms overlap with the new room.
+        for r in mb.rooms.iter() {
+            if r.intersect(&room) {
+                overlap = true
+            }
+        }
+
+        if !overlap {
+            // draw room on map, rooms are lit while corridors are dark.
+            room.for_each(|point| {
+                if mb.map.in_bounds(point) {
+                    let idx = mb.map.point2d_to_index(point);
+                    mb.map.tiles[idx] = TileType::Floor;
+                    mb.map.lit[idx] = true;
+                }
+            });
+
+            // add room
+            mb.rooms.push(room);
+        }
+    }
+}
+
+fn build_corridors(mb: &mut MapBuilder, rng: &mut RandomNumberGenerator) {
+    let mut rooms = mb.rooms.clone();
+    rooms.sort_by(|a, b| a.center().x.cmp(&b.center().x));
+
+    for (i, room) in rooms.iter().enumerate().skip(1) {
+        let prev = rooms[i - 1].center();
+        let new = room.center();
+
+        // 50/50 choice between drawing a corridor elbow up/down and over or
+        // over and up/down
+        if rng.range(0, 2) == 1 {
+            mb.apply_horizontal_tunnel(prev.x, new.x, prev.y);
+            mb.apply_vertical_tunnel(prev.y, new.y, new.x);
+        } else {
+            mb.apply_vertical_tunnel(prev.y, new.y, prev.x);
+            mb.apply_horizontal_tunnel(prev.x, new.x, new.y);
+        }
+    }
+
+    for room in &mb.rooms.clone() {
+        add_doors(mb, room);
+    }
+}
+
+/// Hangs a door wherever a corridor breaks through the wall around a room,
+/// so long as the opening is a single tile wide.
+fn add_doors(mb: &mut MapBuilder, room: &Rect) {
+    let is_wall = |mb: &MapBuilder, pos: Point| {
+        mb.map
+            .try_idx(pos)
+            .is_some_and(|idx| mb.map.tiles[idx] == TileType::Wall)
+    };
+    let is_floor = |mb: &MapBuilder, pos: Point| {
+        mb.map
+            .try_idx(pos)
+            .is_some_and(|idx| mb.map.tiles[idx] == TileType::Floor)
+    };
+
+    // The wall tiles just outside the room, along with the direction the wall
+    // runs in; a doorway needs wall on both sides of it along that line.
+    let horizontal = Point::new(1, 0);
+    let vertical = Point::new(0, 1);
+    let mut edge = Vec::new();
+    for x in room.x1..room.x2 {
+        edge.push((Point::new(x, room.y1 - 1), horizontal));
+        edge.push((Point::new(x, room.y2), horizontal));
+    }
+    for y in room.y1..room.y2 {
+        edge.push((Point::new(room.x1 - 1, y), vertical));
+        edge.push((Point::new(room.x2, y), vertical));
+    }
+
+    for (pos, along) in edge {
+        if is_floor(mb, pos) && is_wall(mb, pos - along) && is_wall(mb, pos + along) {
+            let idx = mb.map.point2d_to_index(pos);
+            mb.map.tiles[idx] = TileType::DoorClosed;
+        }
+    }
+}
+
+/// Turns a few doors into secret doors, so long as that cuts no more than a
+/// couple of rooms off from the player start. Returns the centres of the
+/// rooms that can only be reached by finding a secret door.
+fn hide_doors(mb: &mut MapBuilder, rng: &mut RandomNumberGenerator) -> Vec<Point> {
+    let mut doors: Vec<usize> = (0..mb.map.tiles.len())
+        .filter(|idx| mb.map.tiles[*idx] == TileType::DoorClosed)
+        .collect();
+    let mut secret_doors = 0;
+    let mut secret_rooms = Vec::new();
+
+    while secret_doors < NUM_SECRET_DOORS && !doors.is_empty() {
+        let door = doors.swap_remove(rng.range(0, doors.len()));
+        mb.map.tiles[door] = TileType::SecretDoor;
+
+        let dijkstra_map = DijkstraMap::new(
+            mb.map.width,
+            mb.map.height,
+            &[mb.map.point2d_to_index(mb.player_start)],
+            &mb.map,
+            1024.0,
+        );
+        let cut_off: Vec<Point> = mb
+            .rooms
+            .iter()
+            .map(Rect::center)
+            .filter(|center| dijkstra_map.map[mb.map.point2d_to_index(*center)] >= f32::MAX)
+            .collect();
+        if cut_off.len() > MAX_SECRET_ROOMS {
+            mb.map.tiles[door] = TileType::DoorClosed;
+            continue;
+        }
+        secret_doors += 1;
+        secret_rooms = cut_off;
+    }
+    secret_rooms
+}
@@ -0,0 +1,162 @@
+use super::MapBuilder;
+use crate::prelude::*;
+
+/// Pools tried on every level; some may not fit.
+const POOL_ATTEMPTS: usize = 3;
+const POOL_SIZE: usize = 12;
+const LAVA_SIZE: usize = 8;
+const CHASM_SIZE: usize = 10;
+/// Random spots tried before giving up on placing a chasm.
+const CHASM_ATTEMPTS: usize = 20;
+/// Terrain starts at least this far from the player, so nobody arrives with
+/// their feet in the lava.
+const MIN_START_DISTANCE: f32 = 4.0;
+
+/// Floods a few patches of the level with water, ringed by shallows, or
+/// lava.
+pub fn add_pools(mb: &mut MapBuilder, rng: &mut RandomNumberGenerator) {
+    for _ in 0..POOL_ATTEMPTS {
+        if rng.range(0, 3) == 0 {
+            try_add_blob(mb, rng, TileType::Lava, LAVA_SIZE);
+        } else {
+            try_add_blob(mb, rng, TileType::DeepWater, POOL_SIZE);
+        }
+    }
+}
+
+/// Opens a chasm somewhere on the level, leading to the level below.
+pub fn add_chasm(mb: &mut MapBuilder, rng: &mut RandomNumberGenerator) {
+    for _ in 0..CHASM_ATTEMPTS {
+        if try_add_blob(mb, rng, TileType::Chasm, CHASM_SIZE) {
+            return;
+        }
+    }
+}
+
+/// Grows a blob of `tile` over the floor from a random spot. The blob is
+/// kept only if the player can still walk to everything placed on the
+/// level that they could reach before, without wading in deeper than the
+/// shallows.
+fn try_add_blob(
+    mb: &mut MapBuilder,
+    rng: &mut RandomNumberGenerator,
+    tile: TileType,
+    size: usize,
+) -> bool {
+    let protected = protected_tiles(mb);
+    let can_flood = |mb: &MapBuilder, idx: usize| {
+        mb.map.tiles[idx] == TileType::Floor && !protected.contains(&idx)
+    };
+
+    let floor: Vec<usize> = (0..mb.map.tiles.len())
+        .filter(|idx| {
+            can_flood(mb, *idx)
+                && DistanceAlg::Pythagoras
+                    .distance2d(mb.player_start, mb.map.index_to_point2d(*idx))
+                    >= MIN_START_DISTANCE
+        })
+        .collect();
+    let Some(start) = rng.random_slice_entry(&floor).copied() else {
+        return false;
+    };
+
+    let mut blob = vec![start];
+    for _ in 0..size * 10 {
+        if blob.len() >= size {
+            break;
+        }
+        let from = mb.map.index_to_point2d(blob[rng.range(0, blob.len())]);
+        let next = from + neighbours()[rng.range(0, 4)];
+        if let Some(idx) = mb.map.try_idx(next) {
+            if can_flood(mb, idx) && !blob.contains(&idx) {
+                blob.push(idx);
+            }
+        }
+    }
+
+    let reachable_before = [safely_reachable(mb, false), safely_reachable(mb, true)];
+    let old_tiles = mb.map.tiles.clone();
+    for idx in &blob {
+        mb.map.tiles[*idx] = tile;
+    }
+    // Deep water shelves off gently.
+    if tile == TileType::DeepWater {
+        for idx in &blob {
+            let pos = mb.map.index_to_point2d(*idx);
+            for delta in neighbours() {
+                if let Some(shore) = mb.map.try_idx(pos + delta) {
+                    if can_flood(mb, shore) {
+                        mb.map.tiles[shore] = TileType::ShallowWater;
+                    }
+                }
+            }
+        }
+    }
+
+    // Nothing may be cut off, whether or not the player finds the secret
+    // doors.
+    let reachable_after = [safely_reachable(mb, false), safely_reachable(mb, true)];
+    let cut_off = reachable_before
+        .iter()
+        .zip(&reachable_after)
+        .any(|(before, after)| protected.iter().any(|idx| before[*idx] && !after[*idx]));
+    if cut_off {
+        mb.map.tiles = old_tiles;
+        return false;
+    }
+    true
+}
+
+fn neighbours() -> [Point; 4] {
+    [
+        Point::new(-1, 0),
+        Point::new(1, 0),
+        Point::new(0, -1),
+        Point::new(0, 1),
+    ]
+}
+
+/// Tiles where something starts out, which must stay dry and, if they are
+/// reachable to begin with, reachable.
+pub(super) fn protected_tiles(mb: &MapBuilder) -> Vec<usize> {
+    [
+        mb.player_start,
+        mb.amulet_start,
+        mb.boss_start,
+        mb.companion_start,
+    ]
+    .iter()
+    .chain(&mb.monster_spawns)
+    .chain(&mb.vault_monsters)
+    .chain(&mb.item_spawns)
+    .chain(&mb.trap_spawns)
+    .filter_map(|pos| mb.map.try_idx(*pos))
+    .collect()
+}
+
+/// The tiles the player can walk to, opening doors and wading through
+/// shallows but keeping out of anything worse. Secret doors are only
+/// opened if `through_secret_doors` is set, as though they had been found.
+pub(super) fn safely_reachable(mb: &MapBuilder, through_secret_doors: bool) -> Vec<bool> {
+    let safe = |tile: TileType| {
+        tile.is_walkable()
+            || matches!(tile, TileType::DoorClosed | TileType::ShallowWater)
+            || (through_secret_doors && tile == TileType::SecretDoor)
+    };
+    let mut reached = vec![false; mb.map.tiles.len()];
+    let start = mb.map.point2d_to_index(mb.player_start);
+    reached[start] = true;
+    let mut open = vec![start];
+    while let Some(idx) = open.pop() {
+        let pos = mb.map.index_to_point2d(idx);
+        for delta in neighbours() {
+            if let Some(next) = mb.map.try_idx(pos + delta) {
+                if !reached[next] && safe(mb.map.tiles[next]) {
+                    reached[next] = true;
+                    open.push(next);
+                }
+            }
+        }
+    }
+    reached
+}
@@ -0,0 +1,165 @@
+use crate::prelude::*;
+
+/// The look of a level: which glyph and colours each tile is drawn with, and
+/// which monsters are likely to live there.
+pub trait MapTheme: Sync + Send {
+    fn name(&self) -> &'static str;
+
+    /// How a tile in view of the player is drawn.
+    fn tile_to_render(&self, tile: TileType) -> Render;
+
+    /// Tint for tiles the player remembers but can't currently see.
+    fn remembered_tint(&self) -> RGBA {
+        RGBA::named(DARK_GRAY)
+    }
+
+    /// Relative chance of each kind of monster turning up.
+    fn monster_weights(&self) -> [(MonsterKind, i32); 3];
+}
+
+/// Picks the theme for a new level.
+pub fn random_theme(rng: &mut RandomNumberGenerator) -> Box<dyn MapTheme> {
+    match rng.range(0, 4) {
+        0 => Box::new(CryptTheme {}),
+        1 => Box::new(CaveTheme {}),
+        2 => Box::new(ForestTheme {}),
+        _ => Box::new(LavaTheme {}),
+    }
+}
+
+fn render(glyph: char, color: (u8, u8, u8)) -> Render {
+    Render {
+        color: ColorPair::new(color, BLACK),
+        glyph: to_cp437(glyph),
+    }
+}
+
+/// Tiles that look the same whatever the theme.
+fn common_tile(tile: TileType) -> Render {
+    match tile {
+        TileType::Exit => render('>', WHITE),
+        // The font has no stairs up, so reuse the stairs down in blue.
+        TileType::UpStairs => render('>', LIGHT_BLUE),
+        // Nor does it have doors: they are drawn as wooden walls and floors.
+        TileType::DoorClosed => render('#', SADDLE_BROWN),
+        TileType::DoorOpen => render('.', SADDLE_BROWN),
+        // Liquids are drawn as tinted dirt, and chasms as a floor lost in the
+        // dark.
+        TileType::ShallowWater => render(';', DODGER_BLUE),
+        TileType::DeepWater => render(';', NAVY),
+        TileType::Lava => render(';', CRIMSON),
+        TileType::Chasm => render('.', GRAY15),
+        _ => Render {
+            color: ColorPair::new(WHITE, BLACK),
+            glyph: 32,
+        },
+    }
+}
+
+/// Stone halls haunted by goblin shamans.
+pub struct CryptTheme {}
+
+impl MapTheme for CryptTheme {
+    fn name(&self) -> &'static str {
+        "Crypt"
+    }
+
+    fn tile_to_render(&self, tile: TileType) -> Render {
+        match tile {
+            TileType::Floor => render('.', WHITE),
+            TileType::Wall => render('#', WHITE),
+            _ => common_tile(tile),
+        }
+    }
+
+    fn monster_weights(&self) -> [(MonsterKind, i32); 3] {
+        [
+            (MonsterKind::Goblin, 5),
+            (MonsterKind::GoblinShaman, 3),
+            (MonsterKind::Orc, 2),
+        ]
+    }
+}
+
+/// Damp warrens crawling with goblins.
+pub struct CaveTheme {}
+
+impl MapTheme for CaveTheme {
+    fn name(&self) -> &'static str {
+        "Caves"
+    }
+
+    fn tile_to_render(&self, tile: TileType) -> Render {
+        match tile {
+            TileType::Floor => render(';', GRAY),
+            TileType::Wall => render('#', SLATE_GRAY),
+            _ => common_tile(tile),
+        }
+    }
+
+    fn monster_weights(&self) -> [(MonsterKind, i32); 3] {
+        [
+            (MonsterKind::Goblin, 8),
+            (MonsterKind::GoblinShaman, 1),
+            (MonsterKind::Orc, 1),
+        ]
+    }
+}
+
+/// An overgrown wood, walled in by trees.
+pub struct ForestTheme {}
+
+impl MapTheme for ForestTheme {
+    fn name(&self) -> &'static str {
+        "Forest"
+    }
+
+    fn tile_to_render(&self, tile: TileType) -> Render {
+        match tile {
+            TileType::Floor => render(';', WHITE),
+            TileType::Wall => render('"', WHITE),
+            _ => common_tile(tile),
+        }
+    }
+
+    fn remembered_tint(&self) -> RGBA {
+        RGBA::named(DARKOLIVEGREEN)
+    }
+
+    fn monster_weights(&self) -> [(MonsterKind, i32); 3] {
+        [
+            (MonsterKind::Goblin, 6),
+            (MonsterKind::GoblinShaman, 1),
+            (MonsterKind::Orc, 3),
+        ]
+    }
+}
+
+/// Scorched tunnels where the orcs forge their weapons.
+pub struct LavaTheme {}
+
+impl MapTheme for LavaTheme {
+    fn name(&self) -> &'static str {
+        "Lava Tubes"
+    }
+
+    fn tile_to_render(&self, tile: TileType) -> Render {
+        match tile {
+            TileType::Floor => render(';', ORANGE),
+            TileType::Wall => render('#', ORANGE_RED),
+            _ => common_tile(tile),
+        }
+    }
+
+    fn remembered_tint(&self) -> RGBA {
+        RGBA::named(DARK_RED)
+    }
+
+    fn monster_weights(&self) -> [(MonsterKind, i32); 3] {
+        [
+            (MonsterKind::Goblin, 2),
+            (MonsterKind::GoblinShaman, 1),
+            (MonsterKind::Orc, 7),
+        ]
+    }
+}
@@ -0,0 +1,258 @@
+use super::terrain::{protected_tiles, safely_reachable};
+use super::{MapBuilder, ARCHITECT_NAMES};
+use crate::prelude::*;
+use std::fmt;
+
+/// Least share of the level, in percent, that must be floor for it to be
+/// worth playing.
+const MIN_FLOOR_PERCENT: usize = 10;
+/// The amulet, or the stairs down, must be at least this fraction of the
+/// longer side of the map away from the player start, counted in steps.
+const MIN_AMULET_DISTANCE_DIVISOR: i32 = 4;
+/// Shortest side, in tiles, that every architect can lay out a level on.
+pub const MIN_MAP_SIZE: i32 = 8;
+
+/// Why a level couldn't be generated, or was rejected once built.
+#[derive(Clone, Debug, PartialEq)]
+pub enum MapGenError {
+    /// No architect goes by the name asked for.
+    UnknownArchitect(String),
+    /// The map asked for is too small to lay a level out on.
+    TooSmall { width: i32, height: i32 },
+    /// The player would start on a tile they can't stand on.
+    BadPlayerStart(Point),
+    /// Too little of the level is floor.
+    TooLittleFloor { floor: usize, required: usize },
+    /// Something placed on the level can't be reached from the player start,
+    /// even by finding every secret door.
+    Unreachable(Point),
+    /// The amulet, or the stairs down, can't be reached without finding a
+    /// secret door.
+    AmuletUnreachable(Point),
+    /// The amulet, or the stairs down, is too close to the player start.
+    AmuletTooClose { distance: f32, required: f32 },
+}
+
+impl fmt::Display for MapGenError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MapGenError::UnknownArchitect(name) => write!(
+                f,
+                "there is no {name:?} architect, choose one of {}",
+                ARCHITECT_NAMES.join(", ")
+            ),
+            MapGenError::TooSmall { width, height } => write!(
+                f,
+                "a {width}x{height} map is too small, needed {MIN_MAP_SIZE} tiles a side"
+            ),
+            MapGenError::BadPlayerStart(pos) => {
+                write!(
+                    f,
+                    "the player starts on a tile they can't stand on at {pos:?}"
+                )
+            }
+            MapGenError::TooLittleFloor { floor, required } => {
+                write!(f, "only {floor} floor tiles, needed {required}")
+            }
+            MapGenError::Unreachable(pos) => {
+                write!(f, "{pos:?} can't be reached from the player start")
+            }
+            MapGenError::AmuletUnreachable(pos) => write!(
+                f,
+                "the amulet at {pos:?} can't be reached without a secret door"
+            ),
+            MapGenError::AmuletTooClose { distance, required } => write!(
+                f,
+                "the amulet is {distance} steps from the player start, needed {required}"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for MapGenError {}
+
+/// Checks that a freshly built level is playable: there is enough floor,
+/// everything placed on it can be reached, and the amulet is a fair walk
+/// from the start.
+pub fn validate(mb: &MapBuilder) -> Result<(), MapGenError> {
+    if !mb.map.can_enter_tile(mb.player_start) {
+        return Err(MapGenError::BadPlayerStart(mb.player_start));
+    }
+
+    let floor = mb
+        .map
+        .tiles
+        .iter()
+        .filter(|tile| **tile == TileType::Floor)
+        .count();
+    let required = mb.map.tiles.len() * MIN_FLOOR_PERCENT / 100;
+    if floor < required {
+        return Err(MapGenError::TooLittleFloor { floor, required });
+    }
+
+    let reachable = safely_reachable(mb, true);
+    if let Some(idx) = protected_tiles(mb).into_iter().find(|idx| !reachable[*idx]) {
+        return Err(MapGenError::Unreachable(mb.map.index_to_point2d(idx)));
+    }
+
+    // Secret doors are walls as far as pathing goes, so this is the walk to
+    // the amulet without finding any of them.
+    let dijkstra_map = DijkstraMap::new(
+        mb.map.width,
+        mb.map.height,
+        &[mb.map.point2d_to_index(mb.player_start)],
+        &mb.map,
+        1024.0,
+    );
+    let distance = dijkstra_map.map[mb.map.point2d_to_index(mb.amulet_start)];
+    if distance >= f32::MAX {
+        return Err(MapGenError::AmuletUnreachable(mb.amulet_start));
+    }
+    // Maps are nowhere near `i16::MAX` across, so the conversion is exact.
+    let longer_side = mb.map.width.max(mb.map.height);
+    let required =
+        f32::from(i16::try_from(longer_side / MIN_AMULET_DISTANCE_DIVISOR).unwrap_or(i16::MAX));
+    if distance < required {
+        return Err(MapGenError::AmuletTooClose { distance, required });
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{MapArchitect, MAX_ATTEMPTS};
+    use super::*;
+
+    const WIDTH: i32 = 40;
+    const HEIGHT: i32 = 20;
+
+    /// An open level with the player and the amulet in opposite corners.
+    fn open_level() -> MapBuilder {
+        let mut mb = MapBuilder::empty(WIDTH, HEIGHT);
+        mb.player_start = Point::new(2, 2);
+        mb.amulet_start = Point::new(WIDTH - 3, HEIGHT - 3);
+        mb.boss_start = mb.amulet_start + Point::new(-1, 0);
+        mb.companion_start = mb.player_start + Point::new(1, 0);
+        mb
+    }
+
+    /// Walls the level off down the middle, leaving `gap` in the wall.
+    fn split_level(gap: TileType) -> MapBuilder {
+        let mut mb = open_level();
+        for y in 0..HEIGHT {
+            mb.map.set_tile(Point::new(WIDTH / 2, y), TileType::Wall);
+        }
+        mb.map.set_tile(Point::new(WIDTH / 2, HEIGHT / 2), gap);
+        mb
+    }
+
+    #[test]
+    fn accepts_an_open_level() {
+        assert_eq!(validate(&open_level()), Ok(()));
+    }
+
+    #[test]
+    fn rejects_a_player_start_in_a_wall() {
+        let mut mb = open_level();
+        mb.map.set_tile(mb.player_start, TileType::Wall);
+        assert_eq!(
+            validate(&mb),
+            Err(MapGenError::BadPlayerStart(mb.player_start))
+        );
+    }
+
+    #[test]
+    fn rejects_too_little_floor() {
+        let mut mb = open_level();
+        mb.fill(TileType::Wall);
+        for x in 1..WIDTH - 1 {
+            mb.map.set_tile(Point::new(x, 2), TileType::Floor);
+        }
+        assert!(matches!(
+            validate(&mb),
+            Err(MapGenError::TooLittleFloor { floor: 38, .. })
+        ));
+    }
+
+    #[test]
+    fn rejects_a_monster_walled_off_from_the_player() {
+        let mut mb = split_level(TileType::Wall);
+        mb.amulet_start = Point::new(WIDTH / 2 - 2, HEIGHT - 3);
+        mb.boss_start = mb.amulet_start + Point::new(-1, 0);
+        let monster = Point::new(WIDTH - 3, 2);
+        mb.monster_spawns.push(monster);
+        assert_eq!(validate(&mb), Err(MapGenError::Unreachable(monster)));
+    }
+
+    #[test]
+    fn rejects_an_amulet_behind_a_secret_door() {
+        let mb = split_level(TileType::SecretDoor);
+        assert_eq!(
+            validate(&mb),
+            Err(MapGenError::AmuletUnreachable(mb.amulet_start))
+        );
+    }
+
+    #[test]
+    fn accepts_an_amulet_behind_a_closed_door() {
+        assert_eq!(validate(&split_level(TileType::DoorClosed)), Ok(()));
+    }
+
+    #[test]
+    fn rejects_an_amulet_next_to_the_player() {
+        let mut mb = open_level();
+        mb.amulet_start = mb.player_start + Point::new(0, 1);
+        assert!(matches!(
+            validate(&mb),
+            Err(MapGenError::AmuletTooClose { .. })
+        ));
+    }
+
+    /// Lays out an open level, or, if `broken`, one with the player walled in.
+    struct OpenArchitect {
+        broken: bool,
+    }
+
+    impl MapArchitect for OpenArchitect {
+        fn build(
+            &mut self,
+            _rng: &mut RandomNumberGenerator,
+            width: i32,
+            height: i32,
+        ) -> MapBuilder {
+            let mut mb = MapBuilder::empty(width, height);
+            if self.broken {
+                mb.fill(TileType::Wall);
+            }
+            mb.player_start = Point::new(2, 2);
+            mb.amulet_start = Point::new(width - 3, height - 3);
+            mb
+        }
+    }
+
+    #[test]
+    fn retries_until_a_level_passes() {
+        let mut rng = RandomNumberGenerator::seeded(1);
+        let mut attempts = 0;
+        let result = MapBuilder::with_retries(&mut rng, WIDTH, HEIGHT, |_| {
+            attempts += 1;
+            Box::new(OpenArchitect {
+                broken: attempts < 3,
+            })
+        });
+        assert!(result.is_ok());
+        assert_eq!(attempts, 3);
+    }
+
+    #[test]
+    fn gives_up_with_the_last_failure() {
+        let mut rng = RandomNumberGenerator::seeded(1);
+        let mut attempts = 0;
+        let result = MapBuilder::with_retries(&mut rng, WIDTH, HEIGHT, |_| {
+            attempts += 1;
+            Box::new(OpenArchitect { broken: true })
+        });
+        assert!(matches!(result, Err(MapGenError::BadPlayerStart(_))));
+        assert_eq!(attempts, MAX_ATTEMPTS);
+    }
+}
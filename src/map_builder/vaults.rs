@@ -0,0 +1,96 @@
+use super::MapBuilder;
+use crate::prelude::*;
+
+/// Random spots tried before giving up on fitting a vault into the map.
+const PLACEMENT_ATTEMPTS: usize = 200;
+
+/// Hand-drawn set pieces, written in ASCII: `#` is wall, `.` is floor, `M` is
+/// a monster and `!` is an item, both standing on floor. Any other character
+/// leaves the map underneath untouched.
+const VAULTS: [&str; 2] = [
+    include_str!("../../resources/vaults/treasure.txt"),
+    include_str!("../../resources/vaults/ambush.txt"),
+];
+
+/// Stamps a random vault somewhere it doesn't cut the player off from the
+/// amulet. Levels with no such spot are left without a vault.
+pub fn stamp_random_vault(mb: &mut MapBuilder, rng: &mut RandomNumberGenerator) {
+    let rows: Vec<&str> = VAULTS[rng.random_slice_index(&VAULTS).unwrap()]
+        .lines()
+        .map(str::trim_end)
+        .filter(|row| !row.is_empty())
+        .collect();
+    let width = i32::try_from(rows.iter().map(|row| row.len()).max().unwrap_or(0)).unwrap();
+    let height = i32::try_from(rows.len()).unwrap();
+    if width + 2 >= mb.map.width || height + 2 >= mb.map.height {
+        return;
+    }
+
+    for _ in 0..PLACEMENT_ATTEMPTS {
+        // Keep clear of the map edge, so the level stays walled in.
+        let area = Rect::with_size(
+            rng.range(1, mb.map.width - width),
+            rng.range(1, mb.map.height - height),
+            width,
+            height,
+        );
+        if area.point_in_rect(mb.player_start) || area.point_in_rect(mb.amulet_start) {
+            continue;
+        }
+        if try_stamp(mb, &rows, area) {
+            return;
+        }
+    }
+}
+
+/// Stamps the vault over `area`, keeping it only if the amulet and everything
+/// in the vault can still be reached from the player start.
+fn try_stamp(mb: &mut MapBuilder, rows: &[&str], area: Rect) -> bool {
+    let old_tiles = mb.map.tiles.clone();
+    let mut monsters = Vec::new();
+    let mut items = Vec::new();
+
+    for (y, row) in (area.y1..).zip(rows) {
+        for (x, symbol) in (area.x1..).zip(row.chars()) {
+            let pos = Point::new(x, y);
+            let idx = mb.map.point2d_to_index(pos);
+            match symbol {
+                '#' => mb.map.tiles[idx] = TileType::Wall,
+                '.' => mb.map.tiles[idx] = TileType::Floor,
+                'M' => {
+                    mb.map.tiles[idx] = TileType::Floor;
+                    monsters.push(pos);
+                }
+                '!' => {
+                    mb.map.tiles[idx] = TileType::Floor;
+                    items.push(pos);
+                }
+                _ => {}
+            }
+        }
+    }
+
+    let dijkstra_map = DijkstraMap::new(
+        mb.map.width,
+        mb.map.height,
+        &[mb.map.point2d_to_index(mb.player_start)],
+        &mb.map,
+        1024.0,
+    );
+    let reachable = |pos: &Point| dijkstra_map.map[mb.map.point2d_to_index(*pos)] < f32::MAX;
+    let connected = reachable(&mb.amulet_start)
+        && monsters.iter().all(reachable)
+        && items.iter().all(reachable);
+    if !connected {
+        mb.map.tiles = old_tiles;
+        return false;
+    }
+
+    // The vault brings its own monsters and items, so drop any placed inside
+    // before it was stamped; they could now be standing in its walls.
+    mb.monster_spawns.retain(|pos| !area.point_in_rect(*pos));
+    mb.item_spawns.retain(|pos| !area.point_in_rect(*pos));
+    mb.vault_monsters.extend(monsters);
+    mb.item_spawns.extend(items);
+    true
+}
@@ -0,0 +1,367 @@
+use std::collections::HashMap;
+
+use super::automata::{add_boundaries, find_caves, keep_largest_cave, CellularAutomataArchitect};
+use super::MapArchitect;
+use crate::prelude::*;
+
+/// Width and height of the tile patterns learned from the sample.
+const PATTERN_SIZE: usize = 3;
+/// Contradictions are common enough that a few fresh starts are worth it
+/// before falling back to another architect.
+const ATTEMPTS: usize = 5;
+/// Maps whose connected area is smaller than this are thrown away.
+const MIN_FLOOR_PERCENT: usize = 20;
+/// Scraps of floor smaller than this are filled in rather than joined up.
+const MIN_CAVE_SIZE: usize = 10;
+/// Maps narrower or shorter than this leave too little room for the learned
+/// patterns to fit together, so are left to the cave architect.
+const MIN_WFC_SIZE: i32 = 10;
+
+/// The sample map used when none is given, drawn in the vault format.
+pub const SAMPLE_DUNGEON: &str = include_str!("../../resources/samples/dungeon.txt");
+
+/// Offsets to the neighbouring cells above, right, below and left.
+const DIRECTIONS: [(i32, i32); 4] = [(0, -1), (1, 0), (0, 1), (-1, 0)];
+
+/// Wave Function Collapse: learns which small patterns of wall and floor sit
+/// next to each other in a sample map, then grows a new map that only uses
+/// those patterns. Samples use the vault format, so a vault file works too.
+pub struct WaveFunctionCollapseArchitect {
+    sample: &'static str,
+}
+
+impl WaveFunctionCollapseArchitect {
+    pub fn from_sample(sample: &'static str) -> Self {
+        Self { sample }
+    }
+}
+
+impl MapArchitect for WaveFunctionCollapseArchitect {
+    fn build(&mut self, rng: &mut RandomNumberGenerator, width: i32, height: i32) -> MapBuilder {
+        // Maps every architect can handle but too small for the patterns to
+        // fit together get caves instead.
+        if width < MIN_WFC_SIZE || height < MIN_WFC_SIZE {
+            return CellularAutomataArchitect {}.build(rng, width, height);
+        }
+        let patterns = Patterns::learn(&parse_sample(self.sample));
+        for _ in 0..ATTEMPTS {
+            let Some(tiles) = Wave::new(&patterns, width, height).collapse(&patterns, rng) else {
+                continue;
+            };
+            let mut mb = MapBuilder::empty(width, height);
+            mb.map.tiles = tiles;
+            add_boundaries(&mut mb.map);
+            join_caves(&mut mb);
+            // Anything the tunnels missed is walled off, so everything left is
+            // reachable from the player start.
+            mb.player_start = keep_largest_cave(&mut mb.map);
+            let floor = mb
+                .map
+                .tiles
+                .iter()
+                .filter(|tile| **tile == TileType::Floor)
+                .count();
+            if floor * 100 < mb.map.tiles.len() * MIN_FLOOR_PERCENT {
+                continue;
+            }
+            mb.light_open_areas();
+            mb.amulet_start = mb.find_most_distant();
+            mb.monster_spawns = mb.random_monster_spawns(rng);
+            return mb;
+        }
+
+        CellularAutomataArchitect {}.build(rng, width, height)
+    }
+}
+
+/// Patterns only fit together locally, so the collapsed map tends to come out
+/// as separate areas of floor. Tunnels from each one to the nearest part of
+/// the largest join them into a single dungeon.
+fn join_caves(mb: &mut MapBuilder) {
+    let mut caves = find_caves(&mb.map);
+    caves.sort_by_key(|cave| std::cmp::Reverse(cave.len()));
+    let Some((largest, others)) = caves.split_first() else {
+        return;
+    };
+
+    let mut joined: Vec<Point> = largest
+        .iter()
+        .map(|idx| mb.map.index_to_point2d(*idx))
+        .collect();
+    for cave in others {
+        if cave.len() < MIN_CAVE_SIZE {
+            for idx in cave {
+                mb.map.tiles[*idx] = TileType::Wall;
+            }
+            continue;
+        }
+        let from = mb.map.index_to_point2d(cave[0]);
+        let to = *joined
+            .iter()
+            .min_by_key(|pos| {
+                let delta = **pos - from;
+                delta.x * delta.x + delta.y * delta.y
+            })
+            .unwrap();
+        mb.apply_horizontal_tunnel(from.x, to.x, from.y);
+        mb.apply_vertical_tunnel(from.y, to.y, to.x);
+        joined.extend(cave.iter().map(|idx| mb.map.index_to_point2d(*idx)));
+    }
+}
+
+/// Reads a sample drawn in the vault format into rows of floor (`true`) and
+/// wall (`false`). Monster and item markers count as floor.
+fn parse_sample(sample: &str) -> Vec<Vec<bool>> {
+    sample
+        .lines()
+        .map(str::trim_end)
+        .filter(|row| !row.is_empty())
+        .map(|row| row.chars().map(|symbol| symbol != '#').collect())
+        .collect()
+}
+
+/// Every distinct pattern in the sample, including rotations and
+/// reflections, along with which patterns may overlap each other.
+struct Patterns {
+    tiles: Vec<Vec<bool>>,
+    /// How often each pattern turned up, used to weight the choice between
+    /// them.
+    weights: Vec<usize>,
+    /// `compatible[p][d]` lists the patterns that may sit one cell away from
+    /// `p` in direction `d`.
+    compatible: Vec<[Vec<usize>; 4]>,
+}
+
+impl Patterns {
+    fn learn(sample: &[Vec<bool>]) -> Self {
+        let height = sample.len();
+        let width = sample.iter().map(Vec::len).min().unwrap_or(0);
+        let mut index: HashMap<Vec<bool>, usize> = HashMap::new();
+        let mut tiles: Vec<Vec<bool>> = Vec::new();
+        let mut weights: Vec<usize> = Vec::new();
+
+        // The sample wraps around at its edges, so every pattern has
+        // neighbours on every side.
+        for y in 0..height {
+            for x in 0..width {
+                let mut pattern = Vec::with_capacity(PATTERN_SIZE * PATTERN_SIZE);
+                for py in 0..PATTERN_SIZE {
+                    for px in 0..PATTERN_SIZE {
+                        pattern.push(sample[(y + py) % height][(x + px) % width]);
+                    }
+                }
+                for variant in symmetries(pattern) {
+                    let id = *index.entry(variant.clone()).or_insert_with(|| {
+                        tiles.push(variant);
+                        weights.push(0);
+                        tiles.len() - 1
+                    });
+                    weights[id] += 1;
+                }
+            }
+        }
+
+        let compatible = tiles
+            .iter()
+            .map(|p| {
+                DIRECTIONS.map(|(dx, dy)| {
+                    (0..tiles.len())
+                        .filter(|q| overlaps(p, &tiles[*q], dx, dy))
+                        .collect()
+                })
+            })
+            .collect();
+        Self {
+            tiles,
+            weights,
+            compatible,
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.tiles.len()
+    }
+}
+
+/// The four rotations of a pattern and of its mirror image.
+fn symmetries(pattern: Vec<bool>) -> Vec<Vec<bool>> {
+    let rotate = |p: &[bool]| -> Vec<bool> {
+        (0..PATTERN_SIZE * PATTERN_SIZE)
+            .map(|i| {
+                let (x, y) = (i % PATTERN_SIZE, i / PATTERN_SIZE);
+                p[(PATTERN_SIZE - 1 - x) * PATTERN_SIZE + y]
+            })
+            .collect()
+    };
+    let reflect = |p: &[bool]| -> Vec<bool> {
+        (0..PATTERN_SIZE * PATTERN_SIZE)
+            .map(|i| {
+                let (x, y) = (i % PATTERN_SIZE, i / PATTERN_SIZE);
+                p[y * PATTERN_SIZE + PATTERN_SIZE - 1 - x]
+            })
+            .collect()
+    };
+
+    let mut variants = vec![pattern];
+    for i in 0..3 {
+        variants.push(rotate(&variants[i]));
+    }
+    for i in 0..4 {
+        variants.push(reflect(&variants[i]));
+    }
+    variants
+}
+
+/// Whether pattern `q`, shifted by `(dx, dy)` from pattern `p`, agrees with
+/// `p` everywhere they overlap.
+fn overlaps(p: &[bool], q: &[bool], dx: i32, dy: i32) -> bool {
+    let size = i32::try_from(PATTERN_SIZE).unwrap();
+    let idx = |x: i32, y: i32| usize::try_from(y * size + x).unwrap();
+    (0..size).all(|y| {
+        (0..size).all(|x| {
+            let (qx, qy) = (x - dx, y - dy);
+            qx < 0 || qy < 0 || qx >= size || qy >= size || p[idx(x, y)] == q[idx(qx, qy)]
+        })
+    })
+}
+
+/// The patterns still possible in each cell of the output, where each cell
+/// is the top left corner of one pattern placed on the map.
+struct Wave {
+    width: usize,
+    height: usize,
+    possible: Vec<Vec<bool>>,
+    remaining: Vec<usize>,
+    /// For each cell, pattern and direction, how many patterns still possible
+    /// in the neighbouring cell allow that pattern here. A pattern with no
+    /// support left from any side is ruled out.
+    support: Vec<[usize; 4]>,
+    banned: Vec<(usize, usize)>,
+}
+
+impl Wave {
+    /// A wave for a map of the given size, with one cell wherever a whole
+    /// pattern fits.
+    fn new(patterns: &Patterns, map_width: i32, map_height: i32) -> Self {
+        let width = usize::try_from(map_width).unwrap() - PATTERN_SIZE + 1;
+        let height = usize::try_from(map_height).unwrap() - PATTERN_SIZE + 1;
+        let cells = width * height;
+        let initial_support: Vec<[usize; 4]> = patterns
+            .compatible
+            .iter()
+            .map(|compatible| compatible.each_ref().map(Vec::len))
+            .collect();
+
+        Self {
+            width,
+            height,
+            possible: vec![vec![true; patterns.len()]; cells],
+            remaining: vec![patterns.len(); cells],
+            support: (0..cells)
+                .flat_map(|_| initial_support.iter().copied())
+                .collect(),
+            banned: Vec::new(),
+        }
+    }
+
+    /// Repeatedly settles the least certain cell on one pattern, returning
+    /// the finished tiles, or `None` if some cell ran out of patterns.
+    fn collapse(
+        mut self,
+        patterns: &Patterns,
+        rng: &mut RandomNumberGenerator,
+    ) -> Option<Vec<TileType>> {
+        while let Some(cell) = self.least_certain_cell(rng) {
+            let choices: Vec<usize> = (0..patterns.len())
+                .filter(|p| self.possible[cell][*p])
+                .collect();
+            let total: usize = choices.iter().map(|p| patterns.weights[*p]).sum();
+            let mut roll = rng.range(0, total);
+            let chosen = choices
+                .iter()
+                .copied()
+                .find(|p| {
+                    if roll < patterns.weights[*p] {
+                        return true;
+                    }
+                    roll -= patterns.weights[*p];
+                    false
+                })
+                .unwrap();
+
+            for p in choices.into_iter().filter(|p| *p != chosen) {
+                self.ban(cell, p);
+            }
+            if !self.propagate(patterns) {
+                return None;
+            }
+        }
+        Some(self.tiles(patterns))
+    }
+
+    /// A random cell out of those with the fewest patterns left, or `None` if
+    /// every cell is settled.
+    fn least_certain_cell(&self, rng: &mut RandomNumberGenerator) -> Option<usize> {
+        let fewest = self.remaining.iter().filter(|n| **n > 1).min()?;
+        let candidates: Vec<usize> = (0..self.remaining.len())
+            .filter(|cell| self.remaining[*cell] == *fewest)
+            .collect();
+        rng.random_slice_index(&candidates).map(|i| candidates[i])
+    }
+
+    fn ban(&mut self, cell: usize, pattern: usize) {
+        self.possible[cell][pattern] = false;
+        self.remaining[cell] -= 1;
+        self.banned.push((cell, pattern));
+    }
+
+    /// Rules out patterns that no longer fit next to their neighbours, until
+    /// nothing changes. Returns false on a contradiction.
+    fn propagate(&mut self, patterns: &Patterns) -> bool {
+        let num_patterns = patterns.len();
+        while let Some((cell, pattern)) = self.banned.pop() {
+            if self.remaining[cell] == 0 {
+                return false;
+            }
+            for (direction, (dx, dy)) in DIRECTIONS.iter().enumerate() {
+                let Some(neighbor) = self.neighbor(cell, *dx, *dy) else {
+                    continue;
+                };
+                let opposite = (direction + 2) % 4;
+                for p in &patterns.compatible[pattern][direction] {
+                    let support = &mut self.support[neighbor * num_patterns + p][opposite];
+                    *support -= 1;
+                    if *support == 0 && self.possible[neighbor][*p] {
+                        self.ban(neighbor, *p);
+                    }
+                }
+            }
+        }
+        true
+    }
+
+    fn neighbor(&self, cell: usize, dx: i32, dy: i32) -> Option<usize> {
+        let x = (cell % self.width).checked_add_signed(isize::try_from(dx).unwrap())?;
+        let y = (cell / self.width).checked_add_signed(isize::try_from(dy).unwrap())?;
+        (x < self.width && y < self.height).then_some(y * self.width + x)
+    }
+
+    fn tiles(&self, patterns: &Patterns) -> Vec<TileType> {
+        let map_width = self.width + PATTERN_SIZE - 1;
+        let map_height = self.height + PATTERN_SIZE - 1;
+        let mut tiles = vec![TileType::Wall; map_width * map_height];
+        for (cell, possible) in self.possible.iter().enumerate() {
+            let Some(pattern) = possible.iter().position(|p| *p) else {
+                continue;
+            };
+            let (x, y) = (cell % self.width, cell / self.width);
+            for (i, floor) in patterns.tiles[pattern].iter().enumerate() {
+                let idx = (y + i / PATTERN_SIZE) * map_width + x + i % PATTERN_SIZE;
+                if *floor {
+                    tiles[idx] = TileType::Floor;
+                }
+            }
+        }
+        tiles
+    }
+}
@@ -0,0 +1,56 @@
+use crate::prelude::*;
+
+/// Lists the entities standing on every map tile, so systems can look up what
+/// is at a position without scanning every entity in the world.
+pub struct SpatialIndex {
+    width: i32,
+    height: i32,
+    tile_content: Vec<Vec<Entity>>,
+}
+
+impl SpatialIndex {
+    /// An empty index for a map of the given size in tiles.
+    pub fn new(width: i32, height: i32) -> Self {
+        Self {
+            width,
+            height,
+            tile_content: vec![Vec::new(); usize::try_from(width * height).unwrap()],
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.tile_content.iter_mut().for_each(Vec::clear);
+    }
+
+    pub fn add(&mut self, pos: Point, entity: Entity) {
+        if let Some(idx) = self.try_idx(pos) {
+            self.tile_content[idx].push(entity);
+        }
+    }
+
+    pub fn remove(&mut self, pos: Point, entity: Entity) {
+        if let Some(idx) = self.try_idx(pos) {
+            self.tile_content[idx].retain(|e| *e != entity);
+        }
+    }
+
+    /// Moves an entity between tiles when its position changes.
+    pub fn move_entity(&mut self, entity: Entity, from: Point, to: Point) {
+        self.remove(from, entity);
+        self.add(to, entity);
+    }
+
+    /// All entities positioned on the tile, or none if it is off the map.
+    pub fn entities_at(&self, pos: Point) -> &[Entity] {
+        self.try_idx(pos)
+            .map_or(&[], |idx| self.tile_content[idx].as_slice())
+    }
+
+    fn try_idx(&self, pos: Point) -> Option<usize> {
+        if pos.x >= 0 && pos.x < self.width && pos.y >= 0 && pos.y < self.height {
+            usize::try_from(pos.y * self.width + pos.x).ok()
+        } else {
+            None
+        }
+    }
+}
@@ -1,9 +1,10 @@
 use crate::prelude::*;
 
-pub fn spawn_player(ecs: &mut World, pos: Point) {
-    ecs.push((
+pub fn spawn_player(ecs: &mut World, pos: Point) -> Entity {
+    let player = ecs.push((
         Player,
         pos,
+        BlocksTile,
         Render {
             color: ColorPair::new(WHITE, BLACK),
             glyph: to_cp437('@'),
@@ -13,24 +14,207 @@ pub fn spawn_player(ecs: &mut World, pos: Point) {
             max: 10,
         },
         FieldOfView::new(8),
+        Energy {
+            current: ACTION_COST,
+            speed: NORMAL_SPEED,
+        },
+        Stealth {
+            sneaking: false,
+            noise: 0,
+        },
+    ));
+    if let Some(mut entry) = ecs.entry(player) {
+        entry.add_component(Mobility {
+            swimming: true,
+            ..Mobility::default()
+        });
+    }
+    player
+}
+
+/// Spawns a rescued prisoner who follows the player and fights alongside them.
+pub fn spawn_companion(ecs: &mut World, leader: Entity, pos: Point) {
+    ecs.push((
+        Follower { leader },
+        pos,
+        BlocksTile,
+        Render {
+            color: ColorPair::new(CYAN, BLACK),
+            glyph: to_cp437('@'),
+        },
+        Health { current: 5, max: 5 },
+        Name("Prisoner".to_string()),
+        FieldOfView::new(6),
+        Energy::new(NORMAL_SPEED),
     ));
 }
 
-pub fn spawn_monster(ecs: &mut World, rng: &mut RandomNumberGenerator, pos: Point) {
+/// The kinds of monster a level's theme can favour.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MonsterKind {
+    Goblin,
+    GoblinShaman,
+    Orc,
+}
+
+pub fn spawn_monster(
+    ecs: &mut World,
+    rng: &mut RandomNumberGenerator,
+    theme: &dyn MapTheme,
+    pos: Point,
+) {
+    let entity = spawn_monster_kind(ecs, random_monster_kind(rng, theme), pos);
+    if let Some(mut entry) = ecs.entry(entity) {
+        entry.add_component(ChasingPlayer);
+    }
+}
+
+/// Picks a kind of monster with the level theme's odds.
+fn random_monster_kind(rng: &mut RandomNumberGenerator, theme: &dyn MapTheme) -> MonsterKind {
+    let weights = theme.monster_weights();
+    let mut roll = rng.range(0, weights.iter().map(|(_, weight)| weight).sum());
+    weights
+        .iter()
+        .find(|(_, weight)| {
+            roll -= weight;
+            roll < 0
+        })
+        .map_or(MonsterKind::Goblin, |(kind, _)| *kind)
+}
+
+/// Spawns a monster of the given kind. How it hunts is up to the caller,
+/// which adds either `ChasingPlayer` or a `Pack`.
+fn spawn_monster_kind(ecs: &mut World, kind: MonsterKind, pos: Point) -> Entity {
     // let enemy_glyph = match rng.range(0, 4) {
     //     0 => to_cp437('E'),
     //     1 => to_cp437('O'),
     //     2 => to_cp437('o'),
     //     _ => to_cp437('g'),
     // };
-    let (hp, name, glyph) = match rng.roll_dice(1, 10) {
-        1..=8 => goblin(),
-        _ => orc(),
+    let (hp, name, glyph, speed) = match kind {
+        MonsterKind::Goblin => goblin(),
+        MonsterKind::GoblinShaman => return spawn_goblin_shaman(ecs, pos),
+        MonsterKind::Orc => orc(),
     };
 
-    ecs.push((
+    let entity = ecs.push((
+        Enemy,
+        pos,
+        BlocksTile,
+        Render {
+            color: ColorPair::new(WHITE, BLACK),
+            glyph,
+        },
+        Health {
+            current: hp,
+            max: hp,
+        },
+        Name(name),
+        FieldOfView::new(6),
+    ));
+    // Legion only accepts up to eight components in a single push.
+    if let Some(mut entry) = ecs.entry(entity) {
+        entry.add_component(Energy::new(speed));
+        // Orcs would rather wait for their prey than work a door handle.
+        if kind == MonsterKind::Orc {
+            entry.add_component(Mobility {
+                opens_doors: false,
+                ..Mobility::default()
+            });
+        }
+    }
+    entity
+}
+
+/// Goblin shamans hang back to heal, haste and summon their kin.
+fn spawn_goblin_shaman(ecs: &mut World, pos: Point) -> Entity {
+    let entity = ecs.push((
+        Enemy,
+        pos,
+        BlocksTile,
+        Render {
+            color: ColorPair::new(MAGENTA, BLACK),
+            glyph: to_cp437('g'),
+        },
+        Health { current: 2, max: 2 },
+        Name("Goblin Shaman".to_string()),
+        FieldOfView::new(6),
+    ));
+    if let Some(mut entry) = ecs.entry(entity) {
+        entry.add_component(Energy::new(NORMAL_SPEED));
+        entry.add_component(Spellcaster {
+            spells: vec![Spell::Heal, Spell::Summon, Spell::Haste],
+        });
+        entry.add_component(Mana { current: 4, max: 6 });
+        // Shamans levitate, drifting over water, lava and chasms.
+        entry.add_component(Mobility {
+            flying: true,
+            ..Mobility::default()
+        });
+    }
+    entity
+}
+
+/// Spawns a single monster at `pos`, or, occasionally, a pack gathered around
+/// it, picking every monster with the level theme's odds. Tiles are claimed
+/// in the map's occupancy index as monsters are placed, so neighbouring
+/// spawns never stack.
+pub fn spawn_encounter(
+    ecs: &mut World,
+    rng: &mut RandomNumberGenerator,
+    map: &mut Map,
+    theme: &dyn MapTheme,
+    pos: Point,
+    pack_id: usize,
+) {
+    if map.is_occupied(pos) {
+        return;
+    }
+    if rng.roll_dice(1, 6) == 1 {
+        spawn_pack(ecs, rng, map, theme, pos, pack_id);
+    } else {
+        spawn_monster(ecs, rng, theme, pos);
+        map.set_occupied(pos, true);
+    }
+}
+
+pub fn spawn_pack(
+    ecs: &mut World,
+    rng: &mut RandomNumberGenerator,
+    map: &mut Map,
+    theme: &dyn MapTheme,
+    center: Point,
+    pack_id: usize,
+) {
+    const PACK_SIZE: usize = 3;
+
+    // Gather the pack on free tiles around the spawn point.
+    let mut neighbours: Vec<Point> = (-1..=1)
+        .flat_map(|y| (-1..=1).map(move |x| center + Point::new(x, y)))
+        .filter(|pos| *pos != center && map.can_enter_tile(*pos) && !map.is_occupied(*pos))
+        .collect();
+    let mut positions = vec![center];
+    while positions.len() < PACK_SIZE && !neighbours.is_empty() {
+        let pick = rng.range(0, neighbours.len());
+        positions.push(neighbours.swap_remove(pick));
+    }
+
+    for pos in positions {
+        map.set_occupied(pos, true);
+        let entity = spawn_monster_kind(ecs, random_monster_kind(rng, theme), pos);
+        if let Some(mut entry) = ecs.entry(entity) {
+            entry.add_component(Pack(pack_id));
+        }
+    }
+}
+
+/// Spawns a goblin mid-turn, such as when a boss calls for help.
+pub fn spawn_summoned_goblin(commands: &mut CommandBuffer, pos: Point) {
+    let (hp, name, glyph, speed) = goblin();
+    let entity = commands.push((
         Enemy,
         pos,
+        BlocksTile,
         ChasingPlayer,
         Render {
             color: ColorPair::new(WHITE, BLACK),
@@ -43,13 +227,53 @@ pub fn spawn_monster(ecs: &mut World, rng: &mut RandomNumberGenerator, pos: Poin
         Name(name),
         FieldOfView::new(6),
     ));
+    commands.add_component(entity, Energy::new(speed));
+    // Summoned monsters arrive already knowing who they are fighting.
+    commands.add_component(entity, Aware);
 }
 
-fn goblin() -> (i32, String, FontCharType) {
-    (1, "Goblin".to_string(), to_cp437('g'))
+/// Summons up to `count` goblins onto the free tiles surrounding `center`.
+pub fn summon_goblins(commands: &mut CommandBuffer, map: &mut Map, center: Point, count: usize) {
+    let mut free_tiles: Vec<Point> = (-1..=1)
+        .flat_map(|y| (-1..=1).map(move |x| center + Point::new(x, y)))
+        .filter(|pos| map.can_enter_tile(*pos) && !map.is_occupied(*pos))
+        .collect();
+    free_tiles.truncate(count);
+
+    for pos in free_tiles {
+        spawn_summoned_goblin(commands, pos);
+        map.set_occupied(pos, true);
+    }
+}
+
+/// Spawns the unique boss guarding the Amulet of Yala.
+pub fn spawn_boss(ecs: &mut World, pos: Point) {
+    let entity = ecs.push((
+        Enemy,
+        pos,
+        BlocksTile,
+        ChasingPlayer,
+        Boss {
+            phase: BossPhase::Guarding,
+        },
+        Render {
+            color: ColorPair::new(ORANGE, BLACK),
+            glyph: to_cp437('E'),
+        },
+        Health { current: 8, max: 8 },
+        Name("Ettin Warlord".to_string()),
+    ));
+    if let Some(mut entry) = ecs.entry(entity) {
+        entry.add_component(FieldOfView::new(6));
+        entry.add_component(Energy::new(NORMAL_SPEED));
+    }
+}
+
+fn goblin() -> (i32, String, FontCharType, i32) {
+    (1, "Goblin".to_string(), to_cp437('g'), 12)
 }
-fn orc() -> (i32, String, FontCharType) {
-    (2, "Orc".to_string(), to_cp437('o'))
+fn orc() -> (i32, String, FontCharType, i32) {
+    (2, "Orc".to_string(), to_cp437('o'), 8)
 }
 // fn ogre() -> (i32, String, FontCharType) {
 //     (1, "Ogre".to_string(), to_cp437('O'))
@@ -67,3 +291,80 @@ pub fn spawn_amulet_of_yala(ecs: &mut World, pos: Point) {
         Name("Amulet of Yala".to_string()),
     ));
 }
+
+/// Spawns a hidden trap of a random kind.
+pub fn spawn_trap(ecs: &mut World, rng: &mut RandomNumberGenerator, pos: Point) {
+    let kind = match rng.range(0, 4) {
+        0 => TrapKind::Spikes,
+        1 => TrapKind::Teleport,
+        2 => TrapKind::Alarm,
+        _ => TrapKind::Pit,
+    };
+    let (name, color) = match kind {
+        TrapKind::Spikes => ("Spike Trap", RED),
+        TrapKind::Teleport => ("Teleport Trap", MAGENTA),
+        TrapKind::Alarm => ("Alarm Trap", YELLOW),
+        TrapKind::Pit => ("Pit Trap", DIM_GRAY),
+    };
+    ecs.push((
+        Trap { kind },
+        Hidden,
+        pos,
+        // The font has no trap glyph, so traps are drawn as tinted flagstones.
+        Render {
+            color: ColorPair::new(color, BLACK),
+            glyph: to_cp437('.'),
+        },
+        Name(name.to_string()),
+    ));
+}
+
+/// Spawns a random item, mostly healing potions.
+pub fn spawn_item(ecs: &mut World, rng: &mut RandomNumberGenerator, pos: Point) {
+    match rng.range(0, 10) {
+        0 => spawn_pickaxe(ecs, pos),
+        1 | 2 => spawn_explosives(ecs, pos),
+        _ => spawn_healing_potion(ecs, pos),
+    }
+}
+
+// The font has no tools or bombs, so they borrow the dagger and the potion
+// flask.
+pub fn spawn_pickaxe(ecs: &mut World, pos: Point) {
+    ecs.push((
+        Item,
+        pos,
+        Render {
+            color: ColorPair::new(TAN, BLACK),
+            glyph: to_cp437('s'),
+        },
+        Name("Pickaxe".to_string()),
+        DigsWalls,
+    ));
+}
+
+pub fn spawn_explosives(ecs: &mut World, pos: Point) {
+    ecs.push((
+        Item,
+        pos,
+        Render {
+            color: ColorPair::new(ORANGE_RED, BLACK),
+            glyph: to_cp437('!'),
+        },
+        Name("Blasting Powder".to_string()),
+        Explosive { radius: 2 },
+    ));
+}
+
+pub fn spawn_healing_potion(ecs: &mut World, pos: Point) {
+    ecs.push((
+        Item,
+        pos,
+        Render {
+            color: ColorPair::new(WHITE, BLACK),
+            glyph: to_cp437('!'),
+        },
+        Name("Healing Potion".to_string()),
+        ProvidesHealing { amount: 6 },
+    ));
+}
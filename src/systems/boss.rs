@@ -0,0 +1,28 @@
+use crate::prelude::*;
+
+const MINIONS_SUMMONED: usize = 3;
+
+/// Advances each boss through its scripted phases as it takes damage. The
+/// boss calls in minions at half health and becomes enraged at a quarter.
+#[system]
+#[read_component(Point)]
+#[read_component(Health)]
+#[write_component(Boss)]
+#[write_component(Energy)]
+pub fn boss(ecs: &mut SubWorld, commands: &mut CommandBuffer, #[resource] map: &mut Map) {
+    let mut bosses = <(&Point, &Health, &mut Boss, &mut Energy)>::query();
+
+    bosses
+        .iter_mut(ecs)
+        .for_each(|(pos, health, boss, energy)| {
+            if boss.phase == BossPhase::Guarding && health.current * 2 <= health.max {
+                boss.phase = BossPhase::Rallying;
+                summon_goblins(commands, map, *pos, MINIONS_SUMMONED);
+            }
+
+            if boss.phase == BossPhase::Rallying && health.current * 4 <= health.max {
+                boss.phase = BossPhase::Enraged;
+                energy.speed *= 2;
+            }
+        });
+}
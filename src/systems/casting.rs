@@ -0,0 +1,121 @@
+use crate::prelude::*;
+
+/// A spell paired with the entity it will be cast on.
+type SpellChoice = (Spell, Entity);
+
+/// Lets spellcasters that are ready to act pick a spell. Casting uses up the
+/// caster's action, so the movement AI leaves them alone this turn.
+#[system]
+#[read_component(Point)]
+#[read_component(Player)]
+#[read_component(Enemy)]
+#[read_component(Health)]
+#[read_component(FieldOfView)]
+#[read_component(Spellcaster)]
+#[read_component(Hasted)]
+#[read_component(Aware)]
+#[write_component(Mana)]
+#[write_component(Energy)]
+pub fn casting(ecs: &mut SubWorld, commands: &mut CommandBuffer, #[resource] map: &Map) {
+    let mut casters = <(Entity, &Spellcaster, &Mana, &Energy, &FieldOfView)>::query();
+    let mut player = <&Point>::query().filter(component::<Player>());
+    let player_pos = *player.iter(ecs).next().unwrap();
+
+    // Decide on every caster's spell first, then pay for them.
+    let decisions: Vec<(Entity, i32, Option<SpellChoice>)> = casters
+        .iter(ecs)
+        .filter(|(_, _, _, energy, _)| energy.is_ready())
+        .map(|(entity, caster, mana, _, fov)| {
+            // Casters recover a little mana every time they get to act.
+            let mana = i32::min(mana.max, mana.current + 1);
+            let sees_player = fov.visible_tiles.contains(&player_pos)
+                && ecs
+                    .entry_ref(*entity)
+                    .is_ok_and(|entry| entry.get_component::<Aware>().is_ok());
+            let choice = caster
+                .spells
+                .iter()
+                .filter(|spell| spell.mana_cost() <= mana)
+                .find_map(|spell| choose_target(ecs, map, *entity, *spell, fov, sees_player));
+            (*entity, mana, choice)
+        })
+        .collect();
+
+    for (caster, mana_available, choice) in decisions {
+        let Ok(mut entry) = ecs.entry_mut(caster) else {
+            continue;
+        };
+        let cost = choice.map_or(0, |(spell, _)| spell.mana_cost());
+        if let Ok(mana) = entry.get_component_mut::<Mana>() {
+            mana.current = mana_available - cost;
+        }
+
+        if let Some((spell, target)) = choice {
+            if let Ok(energy) = entry.get_component_mut::<Energy>() {
+                energy.current -= ACTION_COST;
+            }
+            commands.push((
+                (),
+                WantsToCast {
+                    caster,
+                    spell,
+                    target,
+                },
+            ));
+        }
+    }
+}
+
+/// Picks who a spell would be cast on, or `None` if casting it now would be
+/// wasted.
+fn choose_target(
+    ecs: &SubWorld,
+    map: &Map,
+    caster: Entity,
+    spell: Spell,
+    fov: &FieldOfView,
+    sees_player: bool,
+) -> Option<SpellChoice> {
+    let mut allies = <(Entity, &Point, &Health)>::query().filter(component::<Enemy>());
+
+    let target = match spell {
+        Spell::Heal => allies
+            .iter(ecs)
+            .filter(|(_, pos, health)| {
+                health.current < health.max && fov.visible_tiles.contains(pos)
+            })
+            .min_by_key(|(_, _, health)| health.current)
+            .map(|(ally, _, _)| *ally),
+        Spell::Summon => {
+            let caster_pos = ecs
+                .entry_ref(caster)
+                .ok()?
+                .get_component::<Point>()
+                .ok()
+                .copied()?;
+            let has_room = (-1..=1)
+                .flat_map(|y| (-1..=1).map(move |x| caster_pos + Point::new(x, y)))
+                .any(|pos| map.can_enter_tile(pos) && !map.is_occupied(pos));
+            (sees_player && has_room).then_some(caster)
+        }
+        Spell::Haste => {
+            if sees_player {
+                allies
+                    .iter(ecs)
+                    .filter(|(ally, pos, _)| {
+                        **ally != caster
+                            && fov.visible_tiles.contains(pos)
+                            && ecs
+                                .entry_ref(**ally)
+                                .is_ok_and(|entry| entry.get_component::<Hasted>().is_err())
+                    })
+                    .map(|(ally, _, _)| *ally)
+                    .next()
+            } else {
+                None
+            }
+        }
+    };
+
+    target.map(|target| (spell, target))
+}
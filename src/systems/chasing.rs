@@ -3,27 +3,41 @@ use crate::prelude::*;
 #[system]
 #[read_component(Point)]
 #[read_component(Player)]
+#[read_component(Follower)]
 #[read_component(FieldOfView)]
 #[read_component(ChasingPlayer)]
 #[read_component(Health)]
-pub fn chasing(ecs: &SubWorld, commands: &mut CommandBuffer, #[resource] map: &Map) {
-    let mut movers = <(Entity, &Point, &ChasingPlayer, &FieldOfView)>::query();
-    let mut positions = <(Entity, &Point, &Health)>::query();
+#[read_component(Energy)]
+#[read_component(Aware)]
+#[read_component(Mobility)]
+pub fn chasing(
+    ecs: &SubWorld,
+    commands: &mut CommandBuffer,
+    #[resource] map: &Map,
+    #[resource] spatial_index: &SpatialIndex,
+    #[resource] flow_fields: &FlowFieldCache,
+) {
+    let mut movers = <(Entity, &Point, &ChasingPlayer, &FieldOfView, &Energy)>::query()
+        .filter(component::<Aware>());
     let mut player = <(&Point, &Player)>::query();
 
     let player_pos = player.iter(ecs).nth(0).unwrap().0;
-    let player_idx = map_idx(player_pos.x, player_pos.y);
 
-    let search_targets = vec![player_idx];
-    let dijkstra_map = DijkstraMap::new(MAP_WIDTH, MAP_HEIGHT, &search_targets, map, 1024.0);
-
-    movers.iter(ecs).for_each(|(entity, pos, _, fov)| {
-        // Return if monster cannot view the player.
-        if !fov.visible_tiles.contains(&player_pos) {
+    movers.iter(ecs).for_each(|(entity, pos, _, fov, energy)| {
+        // Return if monster has not noticed or cannot view the player, or has
+        // yet to build up the energy to act.
+        if !fov.visible_tiles.contains(&player_pos) || !energy.is_ready() {
             return;
         }
-        let idx = map_idx(pos.x, pos.y);
-        if let Some(destination) = DijkstraMap::find_lowest_exit(&dijkstra_map, idx, map) {
+        let mobility = ecs
+            .entry_ref(*entity)
+            .ok()
+            .and_then(|entry| entry.get_component::<Mobility>().ok().copied())
+            .unwrap_or_default();
+        let dijkstra_map = flow_fields.player_field(mobility);
+        let idx = map.point2d_to_index(*pos);
+        let view = map.view(mobility);
+        if let Some(destination) = DijkstraMap::find_lowest_exit(dijkstra_map, idx, &view) {
             // Checking distance to see if monster is adjacent to player.
             let distance = DistanceAlg::Pythagoras.distance2d(*pos, *player_pos);
             // convert destination to a point, unless it's adjace to player, then
@@ -37,16 +51,19 @@ pub fn chasing(ecs: &SubWorld, commands: &mut CommandBuffer, #[resource] map: &M
             // Determine if movement should be an attack instead by checking
             // if there are any entities at the destination position.
             let mut attacked = false;
-            positions
-                .iter(ecs)
-                .filter(|(_, target_pos, _)| **target_pos == destination)
-                .for_each(|(victim, _, _)| {
-                    // Check if victim is the player and send attack intent.
-                    if ecs
-                        .entry_ref(*victim)
-                        .unwrap()
-                        .get_component::<Player>()
-                        .is_ok()
+            spatial_index
+                .entities_at(destination)
+                .iter()
+                .filter(|victim| {
+                    ecs.entry_ref(**victim)
+                        .is_ok_and(|entry| entry.get_component::<Health>().is_ok())
+                })
+                .for_each(|victim| {
+                    // Check if victim is the player or one of their
+                    // companions and send attack intent.
+                    let victim_entry = ecs.entry_ref(*victim).unwrap();
+                    if victim_entry.get_component::<Player>().is_ok()
+                        || victim_entry.get_component::<Follower>().is_ok()
                     {
                         commands.push((
                             (),
@@ -63,7 +80,7 @@ pub fn chasing(ecs: &SubWorld, commands: &mut CommandBuffer, #[resource] map: &M
                 commands.push((
                     (),
                     WantsToMove {
-                        destination: destination,
+                        destination,
                         entity: *entity,
                     },
                 ));
@@ -1,10 +1,21 @@
 use crate::prelude::*;
 
+/// Damage dealt to a monster that never noticed its attacker.
+const BACKSTAB_DAMAGE: i32 = 3;
+
 #[system]
+#[read_component(Point)]
 #[read_component(Player)]
+#[read_component(Enemy)]
+#[read_component(Aware)]
 #[read_component(WantsToAttack)]
 #[write_component(Health)]
-pub fn combat(ecs: &mut SubWorld, commands: &mut CommandBuffer) {
+pub fn combat(
+    ecs: &mut SubWorld,
+    commands: &mut CommandBuffer,
+    #[resource] map: &mut Map,
+    #[resource] spatial_index: &mut SpatialIndex,
+) {
     let mut attackers = <(Entity, &WantsToAttack)>::query();
     let victims: Vec<(Entity, Entity)> = attackers
         .iter(ecs)
@@ -17,18 +28,43 @@ pub fn combat(ecs: &mut SubWorld, commands: &mut CommandBuffer) {
             .unwrap()
             .get_component::<Player>()
             .is_ok();
+        let victim_pos = ecs
+            .entry_ref(*victim)
+            .unwrap()
+            .get_component::<Point>()
+            .ok()
+            .copied();
+        let (is_enemy, is_aware) = {
+            let entry = ecs.entry_ref(*victim).unwrap();
+            (
+                entry.get_component::<Enemy>().is_ok(),
+                entry.get_component::<Aware>().is_ok(),
+            )
+        };
+        // Unaware monsters are easy prey, but being hit soon wakes them up.
+        let damage = if is_enemy && !is_aware {
+            BACKSTAB_DAMAGE
+        } else {
+            1
+        };
+        if is_enemy {
+            commands.add_component(*victim, Aware);
+        }
 
         if let Ok(mut health) = ecs
             .entry_mut(*victim)
             .unwrap()
             .get_component_mut::<Health>()
         {
-            println!("Health before attack: {}", health.current);
-            health.current -= 1;
+            health.current -= damage;
             if health.current < 1 && !is_player {
                 commands.remove(*victim);
+                // Free the tile so others can step into it this turn.
+                if let Some(pos) = victim_pos {
+                    map.set_occupied(pos, false);
+                    spatial_index.remove(pos, *victim);
+                }
             }
-            println!("Health after attack: {}", health.current);
         }
         commands.remove(*message);
     });
@@ -0,0 +1,49 @@
+use crate::prelude::*;
+
+/// Gives every monster about to act a chance to notice the player. Monsters
+/// can hear a noisy player even when out of sight, and lose track of the
+/// player once they can neither see nor hear them.
+#[system]
+#[read_component(Point)]
+#[read_component(Player)]
+#[read_component(Stealth)]
+#[read_component(Enemy)]
+#[read_component(FieldOfView)]
+#[read_component(Energy)]
+#[read_component(Aware)]
+pub fn detection(ecs: &SubWorld, commands: &mut CommandBuffer, #[resource] map: &Map) {
+    let mut player = <(&Point, &Stealth)>::query().filter(component::<Player>());
+    let mut monsters = <(Entity, &Point, &FieldOfView, &Energy, Option<&Aware>)>::query()
+        .filter(component::<Enemy>());
+    let mut rng = RandomNumberGenerator::new();
+
+    let Some((player_pos, stealth)) = player.iter(ecs).next() else {
+        return;
+    };
+    let exposure = stealth.exposure(map.is_lit(*player_pos));
+
+    monsters
+        .iter(ecs)
+        .filter(|(_, _, _, energy, _)| energy.is_ready())
+        .for_each(|(entity, pos, fov, _, aware)| {
+            let delta = *pos - *player_pos;
+            let distance_squared = delta.x * delta.x + delta.y * delta.y;
+            let heard = distance_squared <= stealth.noise * stealth.noise;
+            let seen = fov.visible_tiles.contains(player_pos);
+
+            if aware.is_some() {
+                if !seen && !heard {
+                    commands.remove_component::<Aware>(*entity);
+                }
+                return;
+            }
+
+            // The further away the monster, the less likely it is to spot the
+            // player.
+            let range_squared = (fov.radius + 1) * (fov.radius + 1);
+            let chance = exposure * (range_squared - distance_squared) / range_squared;
+            if heard || (seen && rng.range(0, 100) < chance) {
+                commands.add_component(*entity, Aware);
+            }
+        });
+}
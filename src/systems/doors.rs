@@ -0,0 +1,24 @@
+use super::fov::mark_views_dirty;
+use crate::prelude::*;
+
+/// Shuts doors the player has asked to close, so long as nothing has stepped
+/// into the doorway since.
+#[system(for_each)]
+#[allow(clippy::trivially_copy_pass_by_ref)] // Legion passes query components by reference.
+#[read_component(FieldOfView)]
+pub fn doors(
+    entity: &Entity,
+    want_close: &WantsToCloseDoor,
+    #[resource] map: &mut Map,
+    ecs: &SubWorld,
+    commands: &mut CommandBuffer,
+) {
+    let open = map
+        .try_idx(want_close.pos)
+        .is_some_and(|idx| map.tiles[idx] == TileType::DoorOpen);
+    if open && !map.is_occupied(want_close.pos) {
+        map.set_tile(want_close.pos, TileType::DoorClosed);
+        mark_views_dirty(ecs, commands, &[want_close.pos]);
+    }
+    commands.remove(*entity);
+}
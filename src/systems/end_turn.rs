@@ -5,24 +5,47 @@ use crate::prelude::*;
 #[read_component(Point)]
 #[read_component(Player)]
 #[read_component(AmuletOfYala)]
-pub fn end_turn(ecs: &SubWorld, #[resource] turn_state: &mut TurnState) {
+#[read_component(Energy)]
+pub fn end_turn(ecs: &SubWorld, #[resource] turn_state: &mut TurnState, #[resource] map: &Map) {
     let mut player_hp = <(&Health, &Point)>::query().filter(component::<Player>());
     let mut amulet = <&Point>::query().filter(component::<AmuletOfYala>());
-    let amulet_pos = amulet.iter(ecs).nth(0).unwrap();
+    // Only the final level has an amulet.
+    let amulet_pos = amulet.iter(ecs).next();
     let current_state = turn_state.clone();
 
     let mut new_state = match current_state {
         TurnState::AwaitingInput => return, // do nothing, exit early.
         TurnState::PlayerTurn => TurnState::MonsterTurn,
-        TurnState::MonsterTurn => TurnState::AwaitingInput,
+        TurnState::MonsterTurn => {
+            // Keep running monster turns until it is the player's go; fast
+            // monsters may act several times in a row.
+            let mut actors = <(&Energy, Option<&Player>)>::query();
+            let monster_ready = actors
+                .iter(ecs)
+                .any(|(energy, player)| player.is_none() && energy.is_ready());
+            let player_ready = actors
+                .iter(ecs)
+                .any(|(energy, player)| player.is_some() && energy.is_ready());
+            if player_ready && !monster_ready {
+                TurnState::AwaitingInput
+            } else {
+                TurnState::MonsterTurn
+            }
+        }
         _ => current_state,
     };
 
     player_hp.iter(ecs).for_each(|(hp, pos)| {
+        match map.try_idx(*pos).map(|idx| map.tiles[idx]) {
+            // Falling through a chasm is a quicker way down than the stairs.
+            Some(TileType::Exit | TileType::Chasm) => new_state = TurnState::NextLevel,
+            Some(TileType::UpStairs) => new_state = TurnState::PreviousLevel,
+            _ => {}
+        }
         if hp.current < 1 {
             new_state = TurnState::GameOver;
         }
-        if pos == amulet_pos {
+        if Some(pos) == amulet_pos {
             new_state = TurnState::Victory;
         }
     });
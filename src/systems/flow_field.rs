@@ -0,0 +1,30 @@
+use std::collections::HashSet;
+
+use crate::prelude::*;
+
+#[system]
+#[read_component(Point)]
+#[read_component(Player)]
+#[read_component(Mobility)]
+pub fn flow_field(
+    ecs: &SubWorld,
+    #[resource] map: &Map,
+    #[resource] flow_fields: &mut FlowFieldCache,
+) {
+    let mut player = <&Point>::query().filter(component::<Player>());
+    let Some(player_pos) = player.iter(ecs).next() else {
+        return;
+    };
+
+    // Walkers have no mobility of their own; build fields for them and for
+    // every other kind of monster on the level.
+    let mut mobilities: HashSet<Mobility> = <&Mobility>::query()
+        .filter(!component::<Player>())
+        .iter(ecs)
+        .copied()
+        .collect();
+    mobilities.insert(Mobility::default());
+    for mobility in mobilities {
+        flow_fields.update(map, *player_pos, mobility);
+    }
+}
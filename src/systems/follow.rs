@@ -0,0 +1,95 @@
+use crate::prelude::*;
+
+/// Companions engage the nearest monster they can see, and otherwise keep
+/// close to their leader.
+#[system]
+#[read_component(Point)]
+#[read_component(Player)]
+#[read_component(Enemy)]
+#[read_component(Follower)]
+#[read_component(FieldOfView)]
+#[read_component(Energy)]
+#[read_component(Mobility)]
+pub fn follow(
+    ecs: &SubWorld,
+    commands: &mut CommandBuffer,
+    #[resource] map: &Map,
+    #[resource] flow_fields: &FlowFieldCache,
+) {
+    let mut followers = <(
+        Entity,
+        &Point,
+        &Follower,
+        &FieldOfView,
+        &Energy,
+        Option<&Mobility>,
+    )>::query();
+    let mut hostiles = <(Entity, &Point)>::query().filter(component::<Enemy>());
+
+    followers
+        .iter(ecs)
+        .filter(|(_, _, _, _, energy, _)| energy.is_ready())
+        .for_each(|(entity, pos, follower, fov, _, mobility)| {
+            let mobility = mobility.copied().unwrap_or_default();
+            let view = map.view(mobility);
+            let Ok(leader) = ecs.entry_ref(follower.leader) else {
+                return;
+            };
+            let Ok(leader_pos) = leader.get_component::<Point>() else {
+                return;
+            };
+            let idx = map.point2d_to_index(*pos);
+
+            let target = hostiles
+                .iter(ecs)
+                .filter(|(_, hostile_pos)| fov.visible_tiles.contains(hostile_pos))
+                .map(|(hostile, hostile_pos)| {
+                    let distance = DistanceAlg::Pythagoras.distance2d(*pos, *hostile_pos);
+                    (*hostile, *hostile_pos, distance)
+                })
+                .min_by(|a, b| a.2.partial_cmp(&b.2).unwrap());
+
+            let next_step = if let Some((hostile, hostile_pos, distance)) = target {
+                if distance < 1.2 {
+                    commands.push((
+                        (),
+                        WantsToAttack {
+                            attacker: *entity,
+                            victim: hostile,
+                        },
+                    ));
+                    return;
+                }
+                step_towards(&view, idx, hostile_pos)
+            } else if DistanceAlg::Pythagoras.distance2d(*pos, *leader_pos) > 2.0 {
+                // The cached flow field already leads to the player.
+                if leader.get_component::<Player>().is_ok() {
+                    DijkstraMap::find_lowest_exit(flow_fields.player_field(mobility), idx, &view)
+                } else {
+                    step_towards(&view, idx, *leader_pos)
+                }
+            } else {
+                None
+            };
+
+            if let Some(next_step) = next_step {
+                commands.push((
+                    (),
+                    WantsToMove {
+                        entity: *entity,
+                        destination: map.index_to_point2d(next_step),
+                    },
+                ));
+            }
+        });
+}
+
+/// First step on the shortest path from the tile at `idx` to `target`.
+fn step_towards(view: &MapView, idx: usize, target: Point) -> Option<usize> {
+    let path = a_star_search(idx, view.point2d_to_index(target), view);
+    if path.success && path.steps.len() > 1 {
+        Some(path.steps[1])
+    } else {
+        None
+    }
+}
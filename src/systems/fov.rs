@@ -13,3 +13,12 @@ pub fn fov(ecs: &mut SubWorld, #[resource] map: &Map) {
             fov.is_dirty = false;
         });
 }
+
+/// Marks dirty the fields of view that take in any of `changed`, such as when
+/// a door opens or a wall comes down, so they are recalculated.
+pub(super) fn mark_views_dirty(ecs: &SubWorld, commands: &mut CommandBuffer, changed: &[Point]) {
+    <(Entity, &FieldOfView)>::query()
+        .iter(ecs)
+        .filter(|(_, fov)| changed.iter().any(|pos| fov.visible_tiles.contains(pos)))
+        .for_each(|(entity, fov)| commands.add_component(*entity, fov.clone_dirty()));
+}
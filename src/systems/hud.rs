@@ -3,15 +3,35 @@ use crate::prelude::*;
 #[system]
 #[read_component(Health)]
 #[read_component(Player)]
-pub fn hud(ecs: &mut SubWorld) {
+#[read_component(Point)]
+#[read_component(Name)]
+#[read_component(Energy)]
+#[read_component(FieldOfView)]
+#[read_component(Boss)]
+#[read_component(Stealth)]
+#[read_component(Aware)]
+#[read_component(Searching)]
+#[read_component(Item)]
+#[read_component(Carried)]
+#[allow(clippy::trivially_copy_pass_by_ref)] // Legion passes resources by reference.
+#[allow(clippy::borrowed_box)] // Legion stores the theme as a boxed resource.
+pub fn hud(
+    ecs: &mut SubWorld,
+    #[resource] map: &Map,
+    #[resource] depth: &Depth,
+    #[resource] theme: &Box<dyn MapTheme>,
+) {
     let mut health_query = <&Health>::query().filter(component::<Player>());
     let player_health = health_query.iter(ecs).nth(0).unwrap();
     let mut draw_batch = DrawBatch::new();
     draw_batch.target(Layers::Info as usize);
-    draw_batch.print_centered(2, "Explore the Dungeon. Cursor keys and WASD to move.");
+    draw_batch.print_centered(
+        2,
+        "Explore the Dungeon. Cursor keys and WASD to move, Z to sneak, G to pick up, C to close doors, F to search.",
+    );
     draw_batch.bar_horizontal(
         Point::zero(),
-        MAP_WIDTH * 2,
+        SCREEN_WIDTH * 2,
         player_health.current,
         player_health.max,
         ColorPair::new(RED, BLACK),
@@ -24,7 +44,143 @@ pub fn hud(ecs: &mut SubWorld) {
         ),
         ColorPair::new(WHITE, RED),
     );
+    draw_batch.print_color_right(
+        Point::new(SCREEN_WIDTH * 2 - 1, 1),
+        format!(
+            "{} - Dungeon Level: {} / {}",
+            theme.name(),
+            depth.0 + 1,
+            NUM_LEVELS
+        ),
+        ColorPair::new(YELLOW, BLACK),
+    );
+    draw_batch.print(
+        Point::new(1, 3),
+        format!("Next to act: {}", next_actor(ecs)),
+    );
+    draw_visibility(ecs, map, &mut draw_batch);
+    draw_boss_health(ecs, &mut draw_batch);
+    draw_inventory(ecs, &mut draw_batch);
     // Number is is intended to be higher than number of tiles in previous
     // two slayers.
     draw_batch.submit(10000).expect("Batch error");
 }
+
+/// Shows how easily monsters will notice the player, and whether any of the
+/// monsters in view already have.
+fn draw_visibility(ecs: &SubWorld, map: &Map, draw_batch: &mut DrawBatch) {
+    let mut player =
+        <(Entity, &Point, &Stealth, &FieldOfView)>::query().filter(component::<Player>());
+    let mut aware = <&Point>::query().filter(component::<Aware>());
+    let Some((player_entity, player_pos, stealth, player_fov)) = player.iter(ecs).next() else {
+        return;
+    };
+    let searching = ecs
+        .entry_ref(*player_entity)
+        .unwrap()
+        .get_component::<Searching>()
+        .is_ok();
+
+    let exposure = stealth.exposure(map.is_lit(*player_pos));
+    let color = match exposure {
+        0..=39 => GREEN,
+        40..=69 => YELLOW,
+        _ => RED,
+    };
+    let spotted = aware
+        .iter(ecs)
+        .any(|pos| player_fov.visible_tiles.contains(pos));
+
+    let mut status = format!("Visibility: {exposure}%");
+    if stealth.sneaking {
+        status.push_str(" (Sneaking)");
+    }
+    if searching {
+        status.push_str(" (Searching)");
+    }
+    if spotted {
+        status.push_str(" - Spotted!");
+    }
+    draw_batch.print_color(Point::new(1, 4), status, ColorPair::new(color, BLACK));
+}
+
+/// Lists the items the player is carrying, numbered by the key that uses
+/// them.
+fn draw_inventory(ecs: &SubWorld, draw_batch: &mut DrawBatch) {
+    let mut player = <Entity>::query().filter(component::<Player>());
+    let Some(player_entity) = player.iter(ecs).next().copied() else {
+        return;
+    };
+    let mut carried_items = <(&Carried, &Name)>::query().filter(component::<Item>());
+
+    let mut y = 7;
+    carried_items
+        .iter(ecs)
+        .filter(|(carried, _)| carried.0 == player_entity)
+        .enumerate()
+        .for_each(|(slot, (_, name))| {
+            draw_batch.print(Point::new(3, y), format!("{} : {}", slot + 1, &name.0));
+            y += 1;
+        });
+    if y > 7 {
+        draw_batch.print_color(
+            Point::new(3, 6),
+            "Items carried",
+            ColorPair::new(YELLOW, BLACK),
+        );
+    }
+}
+
+/// Shows a health bar for any boss the player has spotted or is fighting.
+fn draw_boss_health(ecs: &SubWorld, draw_batch: &mut DrawBatch) {
+    let mut fov = <&FieldOfView>::query().filter(component::<Player>());
+    let player_fov = fov.iter(ecs).next().unwrap();
+    let mut bosses = <(&Boss, &Health, &Name, &Point)>::query();
+
+    bosses
+        .iter(ecs)
+        .filter(|(boss, _, _, pos)| {
+            boss.phase != BossPhase::Guarding || player_fov.visible_tiles.contains(pos)
+        })
+        .for_each(|(boss, health, name, _)| {
+            let (bar_color, label) = match boss.phase {
+                BossPhase::Enraged => (ORANGE, format!(" {} (Enraged)", name.0)),
+                _ => (PURPLE, format!(" {}", name.0)),
+            };
+            draw_batch.bar_horizontal(
+                Point::new(SCREEN_WIDTH / 2, 5),
+                SCREEN_WIDTH,
+                health.current,
+                health.max,
+                ColorPair::new(bar_color, BLACK),
+            );
+            draw_batch.print_color_centered(
+                5,
+                format!("{}: {} / {} ", label, health.current, health.max),
+                ColorPair::new(WHITE, bar_color),
+            );
+        });
+}
+
+/// Name of the actor who will act soonest, out of the player and the monsters
+/// the player can see.
+fn next_actor(ecs: &SubWorld) -> String {
+    let mut fov = <&FieldOfView>::query().filter(component::<Player>());
+    let player_fov = fov.iter(ecs).next().unwrap();
+
+    let mut player = <&Energy>::query().filter(component::<Player>());
+    let mut monsters = <(&Energy, &Point, &Name)>::query().filter(!component::<Player>());
+
+    // The player wins ties, since monsters only act after the player moves.
+    let player_ticks = player
+        .iter(ecs)
+        .next()
+        .map_or(i32::MAX, |energy| energy.ticks_until_ready());
+    monsters
+        .iter(ecs)
+        .filter(|(_, pos, _)| player_fov.visible_tiles.contains(pos))
+        .map(|(energy, _, name)| (energy.ticks_until_ready(), name))
+        .filter(|(ticks, _)| *ticks < player_ticks)
+        .min_by_key(|(ticks, _)| *ticks)
+        .map_or_else(|| "You".to_string(), |(_, name)| name.0.clone())
+}
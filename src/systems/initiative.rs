@@ -0,0 +1,32 @@
+use crate::prelude::*;
+
+/// Advances game time one tick at a time until at least one actor has built up
+/// enough energy to act. Hasted actors gain double energy while the effect
+/// lasts.
+#[system]
+#[write_component(Energy)]
+#[write_component(Hasted)]
+pub fn initiative(ecs: &mut SubWorld, commands: &mut CommandBuffer) {
+    let mut actors = <&mut Energy>::query();
+    let mut hasted = <(Entity, &mut Energy, &mut Hasted)>::query();
+
+    // Nobody would ever become ready, so don't wait for them.
+    if !actors.iter_mut(ecs).any(|energy| energy.speed > 0) {
+        return;
+    }
+
+    while !actors.iter_mut(ecs).any(|energy| energy.is_ready()) {
+        actors
+            .iter_mut(ecs)
+            .for_each(|energy| energy.current += energy.speed);
+        hasted.iter_mut(ecs).for_each(|(_, energy, haste)| {
+            energy.current += energy.speed;
+            haste.ticks_left -= 1;
+        });
+    }
+
+    hasted
+        .iter_mut(ecs)
+        .filter(|(_, _, haste)| haste.ticks_left <= 0)
+        .for_each(|(entity, _, _)| commands.remove_component::<Hasted>(*entity));
+}
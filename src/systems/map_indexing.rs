@@ -0,0 +1,31 @@
+use crate::prelude::*;
+
+/// Rebuilds the tile occupancy and spatial indexes from every positioned
+/// entity, picking up any entities spawned or removed since the last turn,
+/// along with where the revealed traps are.
+#[system]
+#[read_component(Point)]
+#[read_component(BlocksTile)]
+#[read_component(Trap)]
+#[read_component(Hidden)]
+pub fn map_indexing(
+    ecs: &SubWorld,
+    #[resource] map: &mut Map,
+    #[resource] spatial_index: &mut SpatialIndex,
+) {
+    let mut positions = <(Entity, &Point)>::query();
+    let mut blockers = <&Point>::query().filter(component::<BlocksTile>());
+
+    spatial_index.clear();
+    positions
+        .iter(ecs)
+        .for_each(|(entity, pos)| spatial_index.add(*pos, *entity));
+
+    map.clear_occupied();
+    blockers
+        .iter(ecs)
+        .for_each(|pos| map.set_occupied(*pos, true));
+
+    let mut known_traps = <&Point>::query().filter(component::<Trap>() & !component::<Hidden>());
+    map.set_known_traps(known_traps.iter(ecs).copied());
+}
@@ -1,14 +1,30 @@
+mod boss;
+mod casting;
 mod chasing;
 mod combat;
+mod detection;
+mod doors;
 mod end_turn;
 mod entity_render;
+mod flow_field;
+mod follow;
 mod fov;
 mod hud;
+mod initiative;
+mod map_indexing;
 mod map_render;
 mod movement;
+mod pack;
 mod player_input;
 mod random_move;
+mod search;
+mod spells;
+mod spend_energy;
+mod swap;
+mod terrain;
 mod tooltip;
+mod traps;
+mod use_items;
 
 use crate::prelude::*;
 
@@ -26,10 +42,20 @@ pub fn build_input_scheduler() -> Schedule {
 
 pub fn build_player_scheduler() -> Schedule {
     Schedule::builder()
+        .add_system(map_indexing::map_indexing_system())
+        .flush()
+        .add_system(use_items::use_items_system())
+        .add_system(doors::doors_system())
+        .add_system(search::search_system())
         .add_system(combat::combat_system())
         .flush()
+        .add_system(swap::swap_system())
+        .flush()
         .add_system(movement::movement_system())
         .flush() // we want to apply all ecs updates before next system.
+        .add_system(traps::traps_system())
+        .add_system(terrain::terrain_system())
+        .flush()
         .add_system(fov::fov_system())
         .flush()
         .add_system(map_render::map_render_system())
@@ -41,13 +67,34 @@ pub fn build_player_scheduler() -> Schedule {
 
 pub fn build_monster_scheduler() -> Schedule {
     Schedule::builder()
+        .add_system(map_indexing::map_indexing_system())
+        .flush()
+        .add_system(flow_field::flow_field_system())
+        .add_system(initiative::initiative_system())
+        .add_system(boss::boss_system())
+        .add_system(detection::detection_system())
+        .flush()
+        .add_system(casting::casting_system())
+        .flush()
         .add_system(random_move::random_move_system())
         .add_system(chasing::chasing_system())
+        .add_system(pack::pack_system())
+        .add_system(follow::follow_system())
         .flush()
+        .add_system(spend_energy::spend_energy_system())
         .add_system(combat::combat_system())
         .flush()
+        // After combat, so spells never land on monsters killed this turn.
+        .add_system(spells::spells_system())
+        .flush()
         .add_system(movement::movement_system())
         .flush() // we want to apply all ecs updates before next system.
+        .add_system(traps::traps_system())
+        .add_system(terrain::terrain_system())
+        .flush()
+        // Input doesn't rebuild the index, so leave it fresh for the player,
+        // taking in anything summoned this turn.
+        .add_system(map_indexing::map_indexing_system())
         .add_system(fov::fov_system())
         .flush()
         .add_system(map_render::map_render_system())
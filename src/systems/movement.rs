@@ -1,3 +1,4 @@
+use super::fov::mark_views_dirty;
 use crate::prelude::*;
 
 /**
@@ -8,23 +9,104 @@ parameters.
 */
 
 #[system(for_each)]
+#[read_component(Point)]
 #[read_component(Player)]
 #[read_component(FieldOfView)]
+#[read_component(BlocksTile)]
+#[read_component(Trap)]
+#[read_component(Mobility)]
+#[read_component(Carried)]
+#[read_component(DigsWalls)]
 pub fn movement(
     entity: &Entity,
     want_move: &WantsToMove,
     #[resource] map: &mut Map,
     #[resource] camera: &mut Camera,
+    #[resource] spatial_index: &mut SpatialIndex,
     ecs: &mut SubWorld,
     commands: &mut CommandBuffer,
 ) {
-    if map.can_enter_tile(want_move.destination) {
+    // The mover may have been killed earlier in the turn.
+    if ecs.entry_ref(want_move.entity).is_err() {
+        commands.remove(*entity);
+        return;
+    }
+
+    let mobility = ecs
+        .entry_ref(want_move.entity)
+        .ok()
+        .and_then(|entry| entry.get_component::<Mobility>().ok().copied())
+        .unwrap_or_default();
+
+    // Bumping into a closed door spends the move opening it.
+    if map.is_door_closed(want_move.destination) {
+        if mobility.opens_doors {
+            map.set_tile(want_move.destination, TileType::DoorOpen);
+            mark_views_dirty(ecs, commands, &[want_move.destination]);
+        }
+        commands.remove(*entity);
+        return;
+    }
+
+    // Anyone carrying a pickaxe digs through the walls they walk into.
+    if map.is_wall(want_move.destination) && carries_pickaxe(ecs, want_move.entity) {
+        if map.destroy_wall(want_move.destination) {
+            mark_views_dirty(ecs, commands, &[want_move.destination]);
+        }
+        commands.remove(*entity);
+        return;
+    }
+
+    // Moves are applied one at a time, so checking the occupancy index here
+    // stops two entities from landing on the same tile in a single turn.
+    if map.view(mobility).can_enter_tile(want_move.destination)
+        && !map.is_occupied(want_move.destination)
+    {
         // This is updating the Point componenent on the entity.
         // Essentially changing the entity's position.
         // This is the preferred approach over directly editing the value
         // of the reference.
         commands.add_component(want_move.entity, want_move.destination);
 
+        // Keep the occupancy and spatial indexes in sync with the move.
+        if let Ok(entry) = ecs.entry_ref(want_move.entity) {
+            if let Ok(pos) = entry.get_component::<Point>() {
+                let destination_tile = map.tiles[map.point2d_to_index(want_move.destination)];
+                if !mobility.flying && !destination_tile.is_walkable() {
+                    commands.push((
+                        (),
+                        EnteredTerrain {
+                            entity: want_move.entity,
+                            from: *pos,
+                        },
+                    ));
+                }
+                spatial_index.move_entity(want_move.entity, *pos, want_move.destination);
+                if entry.get_component::<BlocksTile>().is_ok() {
+                    map.set_occupied(*pos, false);
+                    map.set_occupied(want_move.destination, true);
+                }
+            }
+        }
+
+        // Any trap on the destination goes off once the move lands.
+        spatial_index
+            .entities_at(want_move.destination)
+            .iter()
+            .filter(|trap| {
+                ecs.entry_ref(**trap)
+                    .is_ok_and(|entry| entry.get_component::<Trap>().is_ok())
+            })
+            .for_each(|trap| {
+                commands.push((
+                    (),
+                    TrapTriggered {
+                        trap: *trap,
+                        victim: want_move.entity,
+                    },
+                ));
+            });
+
         // Mark the FieldOfView as dirty so it can update if the entity moves.
         if let Ok(entry) = ecs.entry_ref(want_move.entity) {
             if let Ok(fov) = entry.get_component::<FieldOfView>() {
@@ -36,7 +118,8 @@ pub fn movement(
 
                     // Marks map tiles as revealed
                     fov.visible_tiles.iter().for_each(|pos| {
-                        map.revealed[map_idx(pos.x, pos.y)] = true;
+                        let idx = map.point2d_to_index(*pos);
+                        map.revealed[idx] = true;
                     })
                 }
             }
@@ -47,6 +130,13 @@ pub fn movement(
     commands.remove(*entity);
 }
 
+fn carries_pickaxe(ecs: &SubWorld, digger: Entity) -> bool {
+    <&Carried>::query()
+        .filter(component::<DigsWalls>())
+        .iter(ecs)
+        .any(|carried| carried.0 == digger)
+}
+
 fn move_camera_if_player_moves(ecs: &mut SubWorld, camera: &mut Camera, want_move: &WantsToMove) {
     // Checking if the entity has the Player component.
     if ecs
@@ -56,3 +146,50 @@ fn move_camera_if_player_moves(ecs: &mut SubWorld, camera: &mut Camera, want_mov
         .is_ok()
     {}
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn two_movers_cannot_stack_on_one_tile() {
+        let mut world = World::default();
+        let mut map = Map::new(10, 10);
+        let mut spatial_index = SpatialIndex::new(10, 10);
+        let destination = Point::new(2, 2);
+        let movers: Vec<Entity> = [Point::new(1, 2), Point::new(3, 2)]
+            .into_iter()
+            .map(|pos| {
+                let mover = world.push((BlocksTile, pos));
+                map.set_occupied(pos, true);
+                spatial_index.add(pos, mover);
+                world.push((
+                    (),
+                    WantsToMove {
+                        entity: mover,
+                        destination,
+                    },
+                ));
+                mover
+            })
+            .collect();
+
+        let mut resources = Resources::default();
+        resources.insert(map);
+        resources.insert(Camera::new(destination));
+        resources.insert(spatial_index);
+        Schedule::builder()
+            .add_system(movement_system())
+            .build()
+            .execute(&mut world, &mut resources);
+
+        let arrived = movers
+            .iter()
+            .filter(|mover| {
+                world.entry(**mover).unwrap().get_component::<Point>() == Ok(&destination)
+            })
+            .count();
+        assert_eq!(arrived, 1);
+        assert!(resources.get::<Map>().unwrap().is_occupied(destination));
+    }
+}
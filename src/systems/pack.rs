@@ -0,0 +1,133 @@
+use std::collections::HashSet;
+
+use crate::prelude::*;
+
+/// Plans the moves of every pack as a group. When any member of a pack has
+/// noticed the player, the whole pack hunts, and each member claims its own
+/// free tile next to the player instead of lining up behind the others.
+#[system]
+#[read_component(Point)]
+#[read_component(Player)]
+#[read_component(Pack)]
+#[read_component(Energy)]
+#[read_component(Aware)]
+#[read_component(Mobility)]
+pub fn pack(
+    ecs: &SubWorld,
+    commands: &mut CommandBuffer,
+    #[resource] map: &Map,
+    #[resource] flow_fields: &FlowFieldCache,
+) {
+    let mut members = <(
+        Entity,
+        &Point,
+        &Pack,
+        &Energy,
+        Option<&Aware>,
+        Option<&Mobility>,
+    )>::query();
+    let mut player = <(Entity, &Point)>::query().filter(component::<Player>());
+
+    let (player_entity, player_pos) = player
+        .iter(ecs)
+        .map(|(entity, pos)| (*entity, *pos))
+        .next()
+        .unwrap();
+
+    // Share target information: a pack hunts if any member has noticed the
+    // player.
+    let hunting_packs: HashSet<usize> = members
+        .iter(ecs)
+        .filter(|(_, _, _, _, aware, _)| aware.is_some())
+        .map(|(_, _, pack, _, _, _)| pack.0)
+        .collect();
+
+    let mut hunters: Vec<(Entity, Point, Mobility)> = members
+        .iter(ecs)
+        .filter(|(_, _, pack, energy, _, _)| hunting_packs.contains(&pack.0) && energy.is_ready())
+        .map(|(entity, pos, _, _, _, mobility)| {
+            (*entity, *pos, mobility.copied().unwrap_or_default())
+        })
+        .collect();
+    // Closest hunters get first pick of the tiles around the player.
+    hunters.sort_by_key(|(_, pos, _)| {
+        let delta = *pos - player_pos;
+        delta.x.abs() + delta.y.abs()
+    });
+
+    // Tiles packmates have moved into or out of this turn, which the
+    // occupancy index will not reflect until the moves are applied.
+    let mut claimed: HashSet<Point> = HashSet::new();
+    let mut vacated: HashSet<Point> = HashSet::new();
+
+    // Tiles next to the player that nobody stands on or has claimed yet, and
+    // that aren't known to be trapped.
+    let mut free_tiles: Vec<Point> = [
+        Point::new(-1, 0),
+        Point::new(1, 0),
+        Point::new(0, -1),
+        Point::new(0, 1),
+    ]
+    .iter()
+    .map(|delta| player_pos + *delta)
+    .filter(|pos| map.can_enter_tile(*pos) && !map.is_occupied(*pos) && !map.is_known_trap(*pos))
+    .collect();
+
+    for (entity, pos, mobility) in hunters {
+        if DistanceAlg::Pythagoras.distance2d(pos, player_pos) < 1.2 {
+            commands.push((
+                (),
+                WantsToAttack {
+                    attacker: entity,
+                    victim: player_entity,
+                },
+            ));
+            continue;
+        }
+
+        // Claim the nearest free tile around the player, or head straight for
+        // the player when every tile has already been claimed.
+        let target = free_tiles
+            .iter()
+            .enumerate()
+            .min_by_key(|(_, tile)| {
+                let delta = **tile - pos;
+                delta.x.abs() + delta.y.abs()
+            })
+            .map(|(i, _)| i)
+            .map(|i| free_tiles.remove(i));
+
+        let view = map.view(mobility);
+        let idx = map.point2d_to_index(pos);
+        let next_step = if let Some(target) = target {
+            let path = a_star_search(idx, map.point2d_to_index(target), &view);
+            if path.success && path.steps.len() > 1 {
+                Some(path.steps[1])
+            } else {
+                None
+            }
+        } else {
+            DijkstraMap::find_lowest_exit(flow_fields.player_field(mobility), idx, &view)
+        };
+        let Some(next_step) = next_step else {
+            continue;
+        };
+
+        // Wait rather than bump into a packmate standing in the way.
+        let destination = map.index_to_point2d(next_step);
+        let blocked = claimed.contains(&destination)
+            || (map.is_occupied(destination) && !vacated.contains(&destination));
+        if blocked {
+            continue;
+        }
+        vacated.insert(pos);
+        claimed.insert(destination);
+        commands.push((
+            (),
+            WantsToMove {
+                entity,
+                destination,
+            },
+        ));
+    }
+}
@@ -1,16 +1,52 @@
 use crate::prelude::*;
 
+/// Turns a search goes on for, unless something interrupts it.
+const SEARCH_TURNS: i32 = 5;
+
 #[system]
 #[read_component(Point)]
 #[read_component(Player)]
 #[read_component(Enemy)]
+#[read_component(Follower)]
+#[read_component(Item)]
+#[read_component(AmuletOfYala)]
+#[read_component(Carried)]
+#[read_component(FieldOfView)]
+#[read_component(Searching)]
 #[write_component(Health)]
+#[write_component(Energy)]
+#[write_component(Stealth)]
 pub fn get_player_input(
     ecs: &mut SubWorld,
     commands: &mut CommandBuffer,
     #[resource] key: &Option<VirtualKeyCode>,
     #[resource] turn_state: &mut TurnState,
+    #[resource] spatial_index: &SpatialIndex,
+    #[resource] map: &Map,
 ) {
+    // A search carries on by itself until it's done, the player presses a
+    // key, or an enemy comes into view.
+    if key.is_none() {
+        let mut players = <(Entity, &FieldOfView)>::query()
+            .filter(component::<Player>() & component::<Searching>());
+        let Some((player_entity, fov)) = players.iter(ecs).next() else {
+            return;
+        };
+        let player_entity = *player_entity;
+        let enemy_in_view = <&Point>::query()
+            .filter(component::<Enemy>())
+            .iter(ecs)
+            .any(|pos| fov.visible_tiles.contains(pos));
+        if enemy_in_view {
+            commands.remove_component::<Searching>(player_entity);
+            return;
+        }
+        rest(ecs, player_entity);
+        spend_player_action(ecs, player_entity, false, false, false);
+        *turn_state = TurnState::PlayerTurn;
+        return;
+    }
+
     if let Some(key) = key {
         let delta = match key {
             VirtualKeyCode::Left => Point::new(-1, 0),
@@ -25,60 +61,351 @@ pub fn get_player_input(
         };
 
         let mut players = <(Entity, &Point)>::query().filter(component::<Player>());
-        let (player_entity, destination) = players
+        let (player_entity, player_pos) = players
             .iter(ecs)
-            .find_map(|(entity, pos)| Some((*entity, *pos + delta)))
+            .map(|(entity, pos)| (*entity, *pos))
+            .next()
             .unwrap();
+        let destination = player_pos + delta;
+
+        // Toggling sneak mode is free and doesn't end the turn.
+        if *key == VirtualKeyCode::Z {
+            toggle_sneaking(ecs, player_entity);
+            return;
+        }
 
+        let sneaking = ecs
+            .entry_ref(player_entity)
+            .unwrap()
+            .get_component::<Stealth>()
+            .is_ok_and(|stealth| stealth.sneaking);
         let mut did_something = false;
+        let mut attacked = false;
+
+        // Anything else the player does breaks off a search.
+        commands.remove_component::<Searching>(player_entity);
+
+        if *key == VirtualKeyCode::G {
+            did_something = pick_up_items(ecs, commands, spatial_index, player_entity, player_pos);
+        } else if *key == VirtualKeyCode::F {
+            commands.add_component(
+                player_entity,
+                Searching {
+                    turns_left: SEARCH_TURNS,
+                },
+            );
+        } else if *key == VirtualKeyCode::C {
+            did_something = close_door(commands, map, spatial_index, player_pos);
+        } else if let Some(slot) = inventory_slot(*key) {
+            did_something = use_item(ecs, commands, player_entity, slot);
+        }
 
         if delta.x != 0 || delta.y != 0 {
-            let mut enemies = <(Entity, &Point)>::query().filter(component::<Enemy>());
+            (did_something, attacked) =
+                bump_or_move(ecs, commands, spatial_index, player_entity, destination);
+        }
+
+        if !did_something {
+            rest(ecs, player_entity);
+        }
+        spend_player_action(ecs, player_entity, did_something, attacked, sneaking);
+
+        *turn_state = TurnState::PlayerTurn;
+    }
+}
 
-            let mut hit_something = false;
+/// Attacks whatever enemy stands at `destination`, swaps places with a
+/// companion there, or otherwise moves onto it. Returns whether the player
+/// did something, and whether that was an attack.
+fn bump_or_move(
+    ecs: &SubWorld,
+    commands: &mut CommandBuffer,
+    spatial_index: &SpatialIndex,
+    player_entity: Entity,
+    destination: Point,
+) -> (bool, bool) {
+    let mut did_something = false;
+    let mut attacked = false;
+    let mut hit_something = false;
 
-            enemies
-                .iter(ecs)
-                .filter(|(_, pos)| {
-                    // return true if an enemy is positioned at the destination.
-                    **pos == destination
+    spatial_index
+        .entities_at(destination)
+        .iter()
+        .filter(|entity| {
+            // return true if the entity at the destination is an enemy.
+            ecs.entry_ref(**entity)
+                .is_ok_and(|entry| entry.get_component::<Enemy>().is_ok())
+        })
+        .for_each(|entity| {
+            did_something = true;
+            hit_something = true;
+            attacked = true;
+            commands.push((
+                (),
+                WantsToAttack {
+                    attacker: player_entity,
+                    victim: *entity,
+                },
+            ));
+        });
+
+    // Step past companions by trading places with them.
+    if !hit_something {
+        spatial_index
+            .entities_at(destination)
+            .iter()
+            .filter(|entity| {
+                ecs.entry_ref(**entity).is_ok_and(|entry| {
+                    entry
+                        .get_component::<Follower>()
+                        .is_ok_and(|follower| follower.leader == player_entity)
                 })
-                .for_each(|(entity, _)| {
-                    did_something = true;
-                    hit_something = true;
-                    commands.push((
-                        (),
-                        WantsToAttack {
-                            attacker: player_entity,
-                            victim: *entity,
-                        },
-                    ));
-                });
-
-            // If nothing is attackable, send normal move intent.
-            if !hit_something {
+            })
+            .take(1)
+            .for_each(|entity| {
                 did_something = true;
+                hit_something = true;
                 commands.push((
                     (),
-                    WantsToMove {
+                    WantsToSwap {
                         entity: player_entity,
-                        destination: destination,
+                        other: *entity,
                     },
                 ));
-            }
-        }
+            });
+    }
 
-        // Heal the player if they waited (did nothing) this turn, unless they are at maximum health.
-        if !did_something {
-            if let Ok(mut health) = ecs
-                .entry_mut(player_entity)
-                .unwrap()
-                .get_component_mut::<Health>()
-            {
-                health.current = i32::min(health.max, health.current + 1);
-            }
-        }
+    // If nothing is attackable, send normal move intent.
+    if !hit_something {
+        did_something = true;
+        commands.push((
+            (),
+            WantsToMove {
+                entity: player_entity,
+                destination,
+            },
+        ));
+    }
 
-        *turn_state = TurnState::PlayerTurn;
+    (did_something, attacked)
+}
+
+/// Puts every item on the player's tile into their inventory, apart from the
+/// amulet, which is claimed by stepping on it.
+fn pick_up_items(
+    ecs: &SubWorld,
+    commands: &mut CommandBuffer,
+    spatial_index: &SpatialIndex,
+    player_entity: Entity,
+    player_pos: Point,
+) -> bool {
+    let items: Vec<Entity> = spatial_index
+        .entities_at(player_pos)
+        .iter()
+        .filter(|entity| {
+            ecs.entry_ref(**entity).is_ok_and(|entry| {
+                entry.get_component::<Item>().is_ok()
+                    && entry.get_component::<AmuletOfYala>().is_err()
+            })
+        })
+        .copied()
+        .collect();
+
+    for item in &items {
+        commands.remove_component::<Point>(*item);
+        commands.add_component(*item, Carried(player_entity));
+    }
+    !items.is_empty()
+}
+
+/// Closes an open door next to the player, if there is one with nothing
+/// standing or lying in the doorway.
+fn close_door(
+    commands: &mut CommandBuffer,
+    map: &Map,
+    spatial_index: &SpatialIndex,
+    player_pos: Point,
+) -> bool {
+    let door = [
+        Point::new(-1, 0),
+        Point::new(1, 0),
+        Point::new(0, -1),
+        Point::new(0, 1),
+    ]
+    .iter()
+    .map(|delta| player_pos + *delta)
+    .find(|pos| {
+        map.try_idx(*pos)
+            .is_some_and(|idx| map.tiles[idx] == TileType::DoorOpen)
+            && spatial_index.entities_at(*pos).is_empty()
+    });
+
+    if let Some(pos) = door {
+        commands.push(((), WantsToCloseDoor { pos }));
+    }
+    door.is_some()
+}
+
+/// Inventory slot picked by the number keys, counting from zero.
+fn inventory_slot(key: VirtualKeyCode) -> Option<usize> {
+    match key {
+        VirtualKeyCode::Key1 => Some(0),
+        VirtualKeyCode::Key2 => Some(1),
+        VirtualKeyCode::Key3 => Some(2),
+        VirtualKeyCode::Key4 => Some(3),
+        VirtualKeyCode::Key5 => Some(4),
+        VirtualKeyCode::Key6 => Some(5),
+        VirtualKeyCode::Key7 => Some(6),
+        VirtualKeyCode::Key8 => Some(7),
+        VirtualKeyCode::Key9 => Some(8),
+        _ => None,
+    }
+}
+
+/// Uses the item in the given inventory slot, in the order the HUD lists
+/// them.
+fn use_item(
+    ecs: &SubWorld,
+    commands: &mut CommandBuffer,
+    player_entity: Entity,
+    slot: usize,
+) -> bool {
+    let mut carried_items = <(Entity, &Carried)>::query().filter(component::<Item>());
+    let item = carried_items
+        .iter(ecs)
+        .filter(|(_, carried)| carried.0 == player_entity)
+        .map(|(entity, _)| *entity)
+        .nth(slot);
+
+    if let Some(item) = item {
+        commands.push((
+            (),
+            ActivateItem {
+                used_by: player_entity,
+                item,
+            },
+        ));
+    }
+    item.is_some()
+}
+
+fn toggle_sneaking(ecs: &mut SubWorld, player_entity: Entity) {
+    if let Ok(stealth) = ecs
+        .entry_mut(player_entity)
+        .unwrap()
+        .get_component_mut::<Stealth>()
+    {
+        stealth.sneaking = !stealth.sneaking;
+    }
+}
+
+/// Heals the player a little for waiting a turn, up to their maximum health.
+fn rest(ecs: &mut SubWorld, player_entity: Entity) {
+    if let Ok(health) = ecs
+        .entry_mut(player_entity)
+        .unwrap()
+        .get_component_mut::<Health>()
+    {
+        health.current = i32::min(health.max, health.current + 1);
+    }
+}
+
+/// Charges the player for their action and records how much noise it made.
+/// Fighting is loud and sneaking is quiet, but slower.
+fn spend_player_action(
+    ecs: &mut SubWorld,
+    player_entity: Entity,
+    did_something: bool,
+    attacked: bool,
+    sneaking: bool,
+) {
+    let (noise, cost) = match (did_something, attacked, sneaking) {
+        (false, _, _) => (0, ACTION_COST),
+        (true, true, _) => (8, ACTION_COST),
+        (true, false, true) => (1, SNEAK_MOVE_COST),
+        (true, false, false) => (4, ACTION_COST),
+    };
+    let mut player = ecs.entry_mut(player_entity).unwrap();
+    if let Ok(stealth) = player.get_component_mut::<Stealth>() {
+        stealth.noise = noise;
+    }
+
+    // Moving, attacking and waiting all use up the player's action.
+    if let Ok(energy) = player.get_component_mut::<Energy>() {
+        energy.current -= cost;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A player standing on a healing potion and the amulet.
+    fn player_on_items(world: &mut World) -> (Entity, Entity, Entity) {
+        let pos = Point::new(2, 2);
+        let player = world.push((
+            Player,
+            pos,
+            Health {
+                current: 5,
+                max: 10,
+            },
+            Energy {
+                current: ACTION_COST,
+                speed: 10,
+            },
+        ));
+        let potion = world.push((Item, pos, ProvidesHealing { amount: 6 }));
+        let amulet = world.push((Item, pos, AmuletOfYala));
+        (player, potion, amulet)
+    }
+
+    fn press(world: &mut World, key: VirtualKeyCode) {
+        let mut spatial_index = SpatialIndex::new(10, 10);
+        <(Entity, &Point)>::query()
+            .iter(world)
+            .for_each(|(entity, pos)| spatial_index.add(*pos, *entity));
+
+        let mut resources = Resources::default();
+        resources.insert(Some(key));
+        resources.insert(TurnState::AwaitingInput);
+        resources.insert(spatial_index);
+        resources.insert(Map::new(10, 10));
+        Schedule::builder()
+            .add_system(get_player_input_system())
+            .build()
+            .execute(world, &mut resources);
+    }
+
+    #[test]
+    fn picks_up_items_but_not_the_amulet() {
+        let mut world = World::default();
+        let (player, potion, amulet) = player_on_items(&mut world);
+        press(&mut world, VirtualKeyCode::G);
+
+        let potion = world.entry(potion).unwrap();
+        assert_eq!(potion.get_component::<Carried>(), Ok(&Carried(player)));
+        assert!(potion.get_component::<Point>().is_err());
+        let amulet = world.entry(amulet).unwrap();
+        assert!(amulet.get_component::<Carried>().is_err());
+        assert!(amulet.get_component::<Point>().is_ok());
+    }
+
+    #[test]
+    fn number_keys_use_carried_items() {
+        let mut world = World::default();
+        let (player, potion, _) = player_on_items(&mut world);
+        world.entry(potion).unwrap().add_component(Carried(player));
+        press(&mut world, VirtualKeyCode::Key1);
+
+        let activations: Vec<ActivateItem> =
+            <&ActivateItem>::query().iter(&world).copied().collect();
+        assert_eq!(
+            activations,
+            vec![ActivateItem {
+                used_by: player,
+                item: potion
+            }]
+        );
     }
 }
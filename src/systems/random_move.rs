@@ -5,11 +5,18 @@ use crate::prelude::*;
 #[read_component(MovingRandmly)]
 #[read_component(Player)]
 #[read_component(Health)]
-pub fn random_move(ecs: &SubWorld, commands: &mut CommandBuffer) {
-    let mut movers = <(Entity, &Point, &MovingRandmly)>::query();
-    let mut positions = <(Entity, &Point, &Health)>::query();
+#[read_component(Energy)]
+pub fn random_move(
+    ecs: &SubWorld,
+    commands: &mut CommandBuffer,
+    #[resource] spatial_index: &SpatialIndex,
+) {
+    let mut movers = <(Entity, &Point, &MovingRandmly, &Energy)>::query();
 
-    movers.iter(ecs).for_each(|(entity, pos, _)| {
+    movers.iter(ecs).for_each(|(entity, pos, _, energy)| {
+        if !energy.is_ready() {
+            return;
+        }
         let mut rng = RandomNumberGenerator::new();
         let move_point = match rng.range(0, 4) {
             0 => Point::new(-1, 0), // left
@@ -20,10 +27,14 @@ pub fn random_move(ecs: &SubWorld, commands: &mut CommandBuffer) {
         let destination = *pos + move_point;
 
         let mut attacked = false;
-        positions
-            .iter(ecs)
-            .filter(|(_, target_pos, _)| **target_pos == destination)
-            .for_each(|(victim, _, _)| {
+        spatial_index
+            .entities_at(destination)
+            .iter()
+            .filter(|victim| {
+                ecs.entry_ref(**victim)
+                    .is_ok_and(|entry| entry.get_component::<Health>().is_ok())
+            })
+            .for_each(|victim| {
                 if ecs
                     .entry_ref(*victim)
                     .unwrap()
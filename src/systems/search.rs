@@ -0,0 +1,120 @@
+use super::fov::mark_views_dirty;
+use crate::prelude::*;
+
+/// One in this many chance, each turn, of finding whatever is hidden on an
+/// adjacent tile.
+const SEARCH_CHANCE: i32 = 3;
+
+/// Checks the tiles around a searching player, turning up secret doors and
+/// hidden traps, and counts down the turns the search has left.
+#[system(for_each)]
+#[allow(clippy::trivially_copy_pass_by_ref)] // Legion passes query components by reference.
+#[filter(component::<Player>())]
+#[read_component(Point)]
+#[read_component(Trap)]
+#[read_component(Hidden)]
+#[read_component(FieldOfView)]
+pub fn search(
+    entity: &Entity,
+    searching: &Searching,
+    #[resource] map: &mut Map,
+    ecs: &SubWorld,
+    commands: &mut CommandBuffer,
+) {
+    let Some(pos) = ecs
+        .entry_ref(*entity)
+        .ok()
+        .and_then(|entry| entry.get_component::<Point>().ok().copied())
+    else {
+        return;
+    };
+    let mut rng = RandomNumberGenerator::new();
+    let found: Vec<Point> = (-1..=1)
+        .flat_map(|dy| (-1..=1).map(move |dx| pos + Point::new(dx, dy)))
+        .filter(|tile| *tile != pos && rng.range(0, SEARCH_CHANCE) == 0)
+        .collect();
+
+    let doors: Vec<Point> = found
+        .iter()
+        .copied()
+        .filter(|tile| {
+            map.try_idx(*tile)
+                .is_some_and(|idx| map.tiles[idx] == TileType::SecretDoor)
+        })
+        .collect();
+    for door in &doors {
+        map.set_tile(*door, TileType::DoorClosed);
+    }
+    if !doors.is_empty() {
+        mark_views_dirty(ecs, commands, &doors);
+    }
+
+    <(Entity, &Point)>::query()
+        .filter(component::<Trap>() & component::<Hidden>())
+        .iter(ecs)
+        .filter(|(_, trap_pos)| found.contains(trap_pos))
+        .for_each(|(trap, _)| commands.remove_component::<Hidden>(*trap));
+
+    if searching.turns_left > 1 {
+        commands.add_component(
+            *entity,
+            Searching {
+                turns_left: searching.turns_left - 1,
+            },
+        );
+    } else {
+        commands.remove_component::<Searching>(*entity);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Runs the search system for a player at `pos` with `turns` of
+    /// searching left, as many times as they have turns.
+    fn search_from(world: &mut World, map: Map, pos: Point, turns: i32) -> (Entity, Map) {
+        let player = world.push((Player, pos, Searching { turns_left: turns }));
+        let mut resources = Resources::default();
+        resources.insert(map);
+        let mut schedule = Schedule::builder().add_system(search_system()).build();
+        for _ in 0..turns {
+            schedule.execute(world, &mut resources);
+        }
+        (player, resources.remove::<Map>().unwrap())
+    }
+
+    #[test]
+    fn searching_finds_secret_doors_and_hidden_traps() {
+        let mut world = World::default();
+        let mut map = Map::new(5, 5);
+        let door = Point::new(3, 2);
+        map.set_tile(door, TileType::SecretDoor);
+        let trap_pos = Point::new(1, 2);
+        let trap = world.push((
+            Trap {
+                kind: TrapKind::Spikes,
+            },
+            Hidden,
+            trap_pos,
+        ));
+
+        // With a one in three chance a turn, missing either for 50 turns is
+        // vanishingly unlikely.
+        let (_, map) = search_from(&mut world, map, Point::new(2, 2), 50);
+        assert!(map.is_door_closed(door));
+        assert!(world
+            .entry(trap)
+            .unwrap()
+            .get_component::<Hidden>()
+            .is_err());
+    }
+
+    #[test]
+    fn searches_end_when_their_turns_run_out() {
+        let mut world = World::default();
+        let (player, _) = search_from(&mut world, Map::new(5, 5), Point::new(2, 2), 3);
+        let player = world.entry(player).unwrap();
+        assert!(player.get_component::<Searching>().is_err());
+    }
+}
@@ -0,0 +1,50 @@
+use crate::prelude::*;
+
+const HEAL_AMOUNT: i32 = 2;
+const GOBLINS_SUMMONED: usize = 2;
+const HASTE_TICKS: i32 = 50;
+
+/// Resolves the spells cast this turn, the way `combat` resolves attacks.
+#[system]
+#[read_component(Point)]
+#[read_component(WantsToCast)]
+#[write_component(Health)]
+pub fn spells(ecs: &mut SubWorld, commands: &mut CommandBuffer, #[resource] map: &mut Map) {
+    let mut casts = <(Entity, &WantsToCast)>::query();
+    let casts: Vec<(Entity, WantsToCast)> = casts
+        .iter(ecs)
+        .map(|(message, cast)| (*message, *cast))
+        .collect();
+
+    for (message, cast) in casts {
+        match cast.spell {
+            Spell::Heal => {
+                if let Ok(mut entry) = ecs.entry_mut(cast.target) {
+                    if let Ok(health) = entry.get_component_mut::<Health>() {
+                        health.current = i32::min(health.max, health.current + HEAL_AMOUNT);
+                    }
+                }
+            }
+            Spell::Summon => {
+                let target_pos = ecs
+                    .entry_ref(cast.target)
+                    .ok()
+                    .and_then(|entry| entry.get_component::<Point>().ok().copied());
+                if let Some(pos) = target_pos {
+                    summon_goblins(commands, map, pos, GOBLINS_SUMMONED);
+                }
+            }
+            Spell::Haste => {
+                if ecs.entry_ref(cast.target).is_ok() {
+                    commands.add_component(
+                        cast.target,
+                        Hasted {
+                            ticks_left: HASTE_TICKS,
+                        },
+                    );
+                }
+            }
+        }
+        commands.remove(message);
+    }
+}
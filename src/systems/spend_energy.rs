@@ -0,0 +1,14 @@
+use crate::prelude::*;
+
+/// Charges every monster that was ready this turn for its action, whether it
+/// moved, attacked or simply waited.
+#[system]
+#[read_component(Player)]
+#[write_component(Energy)]
+pub fn spend_energy(ecs: &mut SubWorld) {
+    let mut actors = <&mut Energy>::query().filter(!component::<Player>());
+    actors
+        .iter_mut(ecs)
+        .filter(|energy| energy.is_ready())
+        .for_each(|energy| energy.current -= ACTION_COST);
+}
@@ -0,0 +1,43 @@
+use crate::prelude::*;
+
+/// Trades the positions of two entities, letting the player step past a
+/// companion instead of attacking it.
+#[system(for_each)]
+#[allow(clippy::trivially_copy_pass_by_ref)] // Legion passes query components by reference.
+#[read_component(Point)]
+#[read_component(Player)]
+#[read_component(FieldOfView)]
+pub fn swap(
+    entity: &Entity,
+    want_swap: &WantsToSwap,
+    #[resource] camera: &mut Camera,
+    #[resource] spatial_index: &mut SpatialIndex,
+    ecs: &mut SubWorld,
+    commands: &mut CommandBuffer,
+) {
+    let position = |e: Entity| {
+        ecs.entry_ref(e)
+            .ok()
+            .and_then(|entry| entry.get_component::<Point>().ok().copied())
+    };
+
+    if let (Some(pos), Some(other_pos)) = (position(want_swap.entity), position(want_swap.other)) {
+        for (mover, from, to) in [
+            (want_swap.entity, pos, other_pos),
+            (want_swap.other, other_pos, pos),
+        ] {
+            commands.add_component(mover, to);
+            spatial_index.move_entity(mover, from, to);
+
+            if let Ok(entry) = ecs.entry_ref(mover) {
+                if let Ok(fov) = entry.get_component::<FieldOfView>() {
+                    commands.add_component(mover, fov.clone_dirty());
+                }
+                if entry.get_component::<Player>().is_ok() {
+                    camera.on_player_move(to);
+                }
+            }
+        }
+    }
+    commands.remove(*entity);
+}
@@ -0,0 +1,80 @@
+use super::traps::wound;
+use crate::prelude::*;
+
+/// Damage dealt by walking into lava.
+const LAVA_DAMAGE: i32 = 2;
+/// Damage dealt to the player by a fall through a chasm.
+const FALL_DAMAGE: i32 = 1;
+/// Extra energy it takes to wade through shallow water.
+const WADING_COST: i32 = ACTION_COST / 2;
+
+/// Applies the effects of the water, lava and chasms creatures moved onto
+/// this turn. The player falling into a chasm is noticed at the end of the
+/// turn, which sends them down a level.
+#[system(for_each)]
+#[allow(clippy::trivially_copy_pass_by_ref)] // Legion passes query components by reference.
+#[read_component(Point)]
+#[read_component(Player)]
+#[read_component(Carried)]
+#[write_component(Health)]
+#[write_component(Energy)]
+pub fn terrain(
+    entity: &Entity,
+    entered: &EnteredTerrain,
+    #[resource] map: &mut Map,
+    #[resource] spatial_index: &mut SpatialIndex,
+    ecs: &mut SubWorld,
+    commands: &mut CommandBuffer,
+) {
+    commands.remove(*entity);
+    let Ok(entry) = ecs.entry_ref(entered.entity) else {
+        return;
+    };
+    let Ok(pos) = entry.get_component::<Point>().copied() else {
+        return;
+    };
+    let is_player = entry.get_component::<Player>().is_ok();
+
+    match map.tiles[map.point2d_to_index(pos)] {
+        TileType::ShallowWater => {
+            if let Ok(mut entry) = ecs.entry_mut(entered.entity) {
+                if let Ok(energy) = entry.get_component_mut::<Energy>() {
+                    energy.current -= WADING_COST;
+                }
+            }
+        }
+        // Swimmers leave whatever they carry on the bank.
+        TileType::DeepWater => {
+            <(Entity, &Carried)>::query()
+                .iter(ecs)
+                .filter(|(_, carried)| carried.0 == entered.entity)
+                .for_each(|(item, _)| {
+                    commands.remove_component::<Carried>(*item);
+                    commands.add_component(*item, entered.from);
+                });
+        }
+        TileType::Lava => wound(
+            ecs,
+            commands,
+            map,
+            spatial_index,
+            entered.entity,
+            LAVA_DAMAGE,
+        ),
+        TileType::Chasm if is_player => wound(
+            ecs,
+            commands,
+            map,
+            spatial_index,
+            entered.entity,
+            FALL_DAMAGE,
+        ),
+        // Monsters that fall are gone from this level for good.
+        TileType::Chasm => {
+            commands.remove(entered.entity);
+            map.set_occupied(pos, false);
+            spatial_index.remove(pos, entered.entity);
+        }
+        _ => {}
+    }
+}
@@ -1,14 +1,19 @@
 use crate::prelude::*;
 
 #[system]
+#[allow(clippy::trivially_copy_pass_by_ref)] // Legion passes resources by reference.
 #[read_component(Point)]
 #[read_component(Name)]
 #[read_component(Health)]
 #[read_component(FieldOfView)]
 #[read_component(Player)]
-pub fn tooltip(ecs: &SubWorld, #[resource] mouse_pos: &Point, #[resource] camera: &Camera) {
-    let mut positions = <(Entity, &Point, &Name)>::query();
-
+#[read_component(Hidden)]
+pub fn tooltip(
+    ecs: &SubWorld,
+    #[resource] mouse_pos: &Point,
+    #[resource] camera: &Camera,
+    #[resource] spatial_index: &SpatialIndex,
+) {
     // For moving the tooltip to display just over the entity's tile.
     let tooltip_overhead_offset = Point::new(0, -2);
 
@@ -21,18 +26,24 @@ pub fn tooltip(ecs: &SubWorld, #[resource] mouse_pos: &Point, #[resource] camera
     let player_fov = fov.iter(ecs).nth(0).unwrap();
 
     draw_batch.target(Layers::Info as usize);
-    positions
-        .iter(ecs)
-        .filter(|(_, pos, _)| **pos == map_pos && player_fov.visible_tiles.contains(&pos))
-        .for_each(|(entity, _, name)| {
-            let screen_pos = *mouse_pos * 4;
-            let display =
-                if let Ok(health) = ecs.entry_ref(*entity).unwrap().get_component::<Health>() {
+    if player_fov.visible_tiles.contains(&map_pos) {
+        spatial_index
+            .entities_at(map_pos)
+            .iter()
+            .filter_map(|entity| ecs.entry_ref(*entity).ok())
+            .filter(|entry| entry.get_component::<Hidden>().is_err())
+            .for_each(|entry| {
+                let Ok(name) = entry.get_component::<Name>() else {
+                    return;
+                };
+                let screen_pos = *mouse_pos * 4;
+                let display = if let Ok(health) = entry.get_component::<Health>() {
                     format!("{} : {} hp", &name.0, health.current)
                 } else {
                     name.0.clone()
                 };
-            draw_batch.print(screen_pos + tooltip_overhead_offset, &display);
-        });
+                draw_batch.print(screen_pos + tooltip_overhead_offset, &display);
+            });
+    }
     draw_batch.submit(10100).expect("Batch error");
 }
@@ -0,0 +1,234 @@
+use crate::prelude::*;
+
+/// Damage dealt by a spike trap.
+const SPIKE_DAMAGE: i32 = 2;
+/// Damage dealt by falling into a pit.
+const PIT_DAMAGE: i32 = 1;
+
+/// Sets off the traps creatures stepped on this turn. A trap the player sees
+/// go off is revealed.
+#[system(for_each)]
+#[allow(clippy::trivially_copy_pass_by_ref)] // Legion passes query components by reference.
+#[read_component(Point)]
+#[read_component(Player)]
+#[read_component(Enemy)]
+#[read_component(Trap)]
+#[read_component(BlocksTile)]
+#[read_component(FieldOfView)]
+#[write_component(Health)]
+#[write_component(Energy)]
+pub fn traps(
+    entity: &Entity,
+    triggered: &TrapTriggered,
+    #[resource] map: &mut Map,
+    #[resource] camera: &mut Camera,
+    #[resource] spatial_index: &mut SpatialIndex,
+    ecs: &mut SubWorld,
+    commands: &mut CommandBuffer,
+) {
+    commands.remove(*entity);
+    let kind = ecs
+        .entry_ref(triggered.trap)
+        .ok()
+        .and_then(|entry| entry.get_component::<Trap>().ok().map(|trap| trap.kind));
+    let victim_pos = ecs
+        .entry_ref(triggered.victim)
+        .ok()
+        .and_then(|entry| entry.get_component::<Point>().ok().copied());
+    let (Some(kind), Some(victim_pos)) = (kind, victim_pos) else {
+        return;
+    };
+
+    // The player's view is still being recalculated if they just moved, but
+    // they surely notice a trap they stepped on themselves.
+    let mut player_fov = <(Entity, &FieldOfView)>::query().filter(component::<Player>());
+    let seen = player_fov.iter(ecs).any(|(player, fov)| {
+        *player == triggered.victim || fov.visible_tiles.contains(&victim_pos)
+    });
+    if seen {
+        commands.remove_component::<Hidden>(triggered.trap);
+    }
+
+    match kind {
+        TrapKind::Spikes => wound(
+            ecs,
+            commands,
+            map,
+            spatial_index,
+            triggered.victim,
+            SPIKE_DAMAGE,
+        ),
+        TrapKind::Pit => {
+            wound(
+                ecs,
+                commands,
+                map,
+                spatial_index,
+                triggered.victim,
+                PIT_DAMAGE,
+            );
+            let Ok(mut entry) = ecs.entry_mut(triggered.victim) else {
+                return;
+            };
+            if let Ok(energy) = entry.get_component_mut::<Energy>() {
+                energy.current -= ACTION_COST;
+            }
+        }
+        TrapKind::Teleport => teleport(ecs, commands, map, camera, spatial_index, triggered.victim),
+        TrapKind::Alarm => {
+            <Entity>::query()
+                .filter(component::<Enemy>())
+                .iter(ecs)
+                .for_each(|monster| commands.add_component(*monster, Aware));
+        }
+    }
+}
+
+/// Hurts the victim, removing them if it kills them. The player's death is
+/// left for the end of the turn to notice.
+pub(super) fn wound(
+    ecs: &mut SubWorld,
+    commands: &mut CommandBuffer,
+    map: &mut Map,
+    spatial_index: &mut SpatialIndex,
+    victim: Entity,
+    damage: i32,
+) {
+    let Ok(mut entry) = ecs.entry_mut(victim) else {
+        return;
+    };
+    let is_player = entry.get_component::<Player>().is_ok();
+    let pos = entry.get_component::<Point>().ok().copied();
+    if let Ok(health) = entry.get_component_mut::<Health>() {
+        health.current -= damage;
+        if health.current < 1 && !is_player {
+            commands.remove(victim);
+            if let Some(pos) = pos {
+                map.set_occupied(pos, false);
+                spatial_index.remove(pos, victim);
+            }
+        }
+    }
+}
+
+/// Moves the victim to a random free floor tile.
+fn teleport(
+    ecs: &SubWorld,
+    commands: &mut CommandBuffer,
+    map: &mut Map,
+    camera: &mut Camera,
+    spatial_index: &mut SpatialIndex,
+    victim: Entity,
+) {
+    let free_tiles: Vec<Point> = map
+        .tiles
+        .iter()
+        .enumerate()
+        .filter(|(idx, tile)| {
+            **tile == TileType::Floor
+                && !map.occupied[*idx]
+                && spatial_index
+                    .entities_at(map.index_to_point2d(*idx))
+                    .is_empty()
+        })
+        .map(|(idx, _)| map.index_to_point2d(idx))
+        .collect();
+    let mut rng = RandomNumberGenerator::new();
+    let Some(destination) = rng.random_slice_entry(&free_tiles).copied() else {
+        return;
+    };
+
+    let entry = ecs.entry_ref(victim).unwrap();
+    let Ok(pos) = entry.get_component::<Point>() else {
+        return;
+    };
+    commands.add_component(victim, destination);
+    spatial_index.move_entity(victim, *pos, destination);
+    if entry.get_component::<BlocksTile>().is_ok() {
+        map.set_occupied(*pos, false);
+        map.set_occupied(destination, true);
+    }
+    if let Ok(fov) = entry.get_component::<FieldOfView>() {
+        commands.add_component(victim, fov.clone_dirty());
+    }
+    if entry.get_component::<Player>().is_ok() {
+        camera.on_player_move(destination);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::movement::movement_system;
+    use super::*;
+
+    const TRAP_POS: Point = Point { x: 2, y: 2 };
+
+    /// Moves `victim` onto a hidden spike trap, then returns its health and
+    /// whether the trap is still hidden.
+    fn step_on_trap(mut world: World, victim: Entity) -> (i32, bool) {
+        let trap = world.push((
+            Trap {
+                kind: TrapKind::Spikes,
+            },
+            Hidden,
+            TRAP_POS,
+        ));
+        world.push((
+            (),
+            WantsToMove {
+                entity: victim,
+                destination: TRAP_POS,
+            },
+        ));
+        let mut spatial_index = SpatialIndex::new(10, 10);
+        <(Entity, &Point)>::query()
+            .iter(&world)
+            .for_each(|(entity, pos)| spatial_index.add(*pos, *entity));
+
+        let mut resources = Resources::default();
+        resources.insert(Map::new(10, 10));
+        resources.insert(Camera::new(TRAP_POS));
+        resources.insert(spatial_index);
+        Schedule::builder()
+            .add_system(movement_system())
+            .flush()
+            .add_system(traps_system())
+            .build()
+            .execute(&mut world, &mut resources);
+
+        let victim = world.entry(victim).unwrap();
+        let health = victim.get_component::<Health>().unwrap().current;
+        let hidden = world.entry(trap).unwrap().get_component::<Hidden>().is_ok();
+        (health, hidden)
+    }
+
+    #[test]
+    fn the_player_sets_off_and_reveals_a_hidden_trap() {
+        let mut world = World::default();
+        let player = world.push((
+            Player,
+            Point::new(1, 2),
+            Health {
+                current: 10,
+                max: 10,
+            },
+            FieldOfView::new(8),
+        ));
+        assert_eq!(step_on_trap(world, player), (10 - SPIKE_DAMAGE, false));
+    }
+
+    #[test]
+    fn traps_sprung_out_of_sight_stay_hidden() {
+        let mut world = World::default();
+        world.push((Player, Point::new(8, 8), FieldOfView::new(8)));
+        let monster = world.push((
+            Enemy,
+            Point::new(1, 2),
+            Health {
+                current: 10,
+                max: 10,
+            },
+        ));
+        assert_eq!(step_on_trap(world, monster), (10 - SPIKE_DAMAGE, true));
+    }
+}
@@ -0,0 +1,118 @@
+use super::fov::mark_views_dirty;
+use crate::prelude::*;
+
+#[system]
+#[read_component(ActivateItem)]
+#[read_component(ProvidesHealing)]
+#[read_component(Explosive)]
+#[read_component(DigsWalls)]
+#[read_component(Point)]
+#[read_component(FieldOfView)]
+#[write_component(Health)]
+pub fn use_items(ecs: &mut SubWorld, commands: &mut CommandBuffer, #[resource] map: &mut Map) {
+    let mut activations = <(Entity, &ActivateItem)>::query();
+    let mut healing_to_apply = Vec::new();
+    let mut blasts = Vec::new();
+
+    activations.iter(ecs).for_each(|(message, activate)| {
+        commands.remove(*message);
+        let Ok(item) = ecs.entry_ref(activate.item) else {
+            return;
+        };
+        // Tools are put to work by walking into things, and are never used
+        // up.
+        if item.get_component::<DigsWalls>().is_ok() {
+            return;
+        }
+        if let Ok(healing) = item.get_component::<ProvidesHealing>() {
+            healing_to_apply.push((activate.used_by, healing.amount));
+        }
+        if let Ok(explosive) = item.get_component::<Explosive>() {
+            if let Ok(user) = ecs.entry_ref(activate.used_by) {
+                if let Ok(pos) = user.get_component::<Point>() {
+                    blasts.push((*pos, explosive.radius));
+                }
+            }
+        }
+        // Items are used up when activated.
+        commands.remove(activate.item);
+    });
+
+    for (target, amount) in healing_to_apply {
+        if let Ok(mut entry) = ecs.entry_mut(target) {
+            if let Ok(health) = entry.get_component_mut::<Health>() {
+                health.current = i32::min(health.max, health.current + amount);
+            }
+        }
+    }
+
+    for (center, radius) in blasts {
+        let destroyed: Vec<Point> = (-radius..=radius)
+            .flat_map(|y| (-radius..=radius).map(move |x| Point::new(x, y)))
+            .filter(|delta| delta.x * delta.x + delta.y * delta.y <= radius * radius)
+            .map(|delta| center + delta)
+            .filter(|pos| map.destroy_wall(*pos))
+            .collect();
+        mark_views_dirty(ecs, commands, &destroyed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Has `user` use up `item`, then returns their health.
+    fn use_item(world: &mut World, user: Entity, item: Entity) -> i32 {
+        world.push((
+            (),
+            ActivateItem {
+                used_by: user,
+                item,
+            },
+        ));
+        let mut resources = Resources::default();
+        resources.insert(Map::new(10, 10));
+        Schedule::builder()
+            .add_system(use_items_system())
+            .build()
+            .execute(world, &mut resources);
+        world
+            .entry(user)
+            .unwrap()
+            .get_component::<Health>()
+            .unwrap()
+            .current
+    }
+
+    #[test]
+    fn potions_heal_and_are_used_up() {
+        let mut world = World::default();
+        let player = world.push((
+            Player,
+            Health {
+                current: 2,
+                max: 10,
+            },
+        ));
+        let potion = world.push((Item, ProvidesHealing { amount: 6 }, Carried(player)));
+
+        assert_eq!(use_item(&mut world, player, potion), 8);
+        assert!(world.entry(potion).is_none());
+        assert_eq!(<&ActivateItem>::query().iter(&world).count(), 0);
+    }
+
+    #[test]
+    fn healing_stops_at_full_health() {
+        let mut world = World::default();
+        let player = world.push((
+            Player,
+            Health {
+                current: 7,
+                max: 10,
+            },
+        ));
+        let potion = world.push((Item, ProvidesHealing { amount: 6 }, Carried(player)));
+
+        assert_eq!(use_item(&mut world, player, potion), 10);
+    }
+}
@@ -5,4 +5,8 @@ pub enum TurnState {
     MonsterTurn,
     GameOver,
     Victory,
+    /// The player took the stairs down and a new level needs building.
+    NextLevel,
+    /// The player took the stairs back up to the level above.
+    PreviousLevel,
 }